@@ -0,0 +1,60 @@
+import { ApprovalRule } from "./gitlab";
+
+// Rule types whose approvers are inherently anyone with access to the project.
+const ANY_APPROVER = "any_approver";
+
+/**
+ * The result of checking a single approval rule
+ */
+export interface RuleAudit {
+  readonly rule: ApprovalRule;
+  /** Why the rule can't be satisfied; empty if it can */
+  readonly problems: Array<string>;
+}
+
+/**
+ * Checks each of the project's approval rules for misconfigurations that make
+ * it impossible to satisfy, and so silently block every MR it applies to.
+ *
+ * @param {Array<ApprovalRule>} rules - the project's approval rules
+ * @returns {Array<RuleAudit>} one result per rule, in the given order
+ */
+export function auditRules(rules: ReadonlyArray<ApprovalRule>): Array<RuleAudit> {
+  return rules.map((rule) => {
+    const problems: Array<string> = [];
+    const required = rule.approvals_required;
+    const eligible = rule.eligible_approvers?.length ?? 0;
+
+    if (required > 0 && rule.rule_type !== ANY_APPROVER) {
+      if (eligible === 0) {
+        problems.push("no eligible approvers");
+      } else if (eligible < required) {
+        problems.push(`requires ${required} approvals, but only ${eligible} eligible`);
+      }
+    }
+
+    return { rule, problems };
+  });
+}
+
+/**
+ * Formats audit results as plain text, one pass/fail line per rule.
+ *
+ * @param {Array<RuleAudit>} audits - audit results
+ * @returns {string} formatted text
+ */
+export function formatAudit(audits: ReadonlyArray<RuleAudit>): string {
+  if (audits.length === 0) return "No approval rules configured.\n";
+
+  return audits
+    .map(({ rule, problems }) => {
+      const status = problems.length > 0 ? "FAIL" : "PASS";
+      const needs =
+        rule.rule_type === ANY_APPROVER
+          ? `${rule.approvals_required} from anyone`
+          : `${rule.approvals_required} of ${rule.eligible_approvers?.length ?? 0} eligible`;
+      const detail = problems.length > 0 ? `: ${problems.join(", ")}` : "";
+      return `${status} ${rule.name} (${needs})${detail}\n`;
+    })
+    .join("");
+}
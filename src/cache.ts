@@ -0,0 +1,91 @@
+import { readFile, writeFile } from "fs/promises";
+
+import { Author } from "./gitlab";
+import { log } from "./util";
+
+/**
+ * The approval data cached for each MR
+ */
+export interface Approvals {
+  /** `null` when GitLab hasn't computed it, e.g. for newly configured rules */
+  readonly approvals_left: number | null;
+  /** Eligible approvers who haven't yet approved */
+  readonly pending_approvers: ReadonlyArray<Author>;
+}
+
+interface CacheEntry extends Approvals {
+  readonly updated_at: string;
+}
+
+/**
+ * Persistent cache of MR approval data.
+ *
+ * Approvals rarely change unless the MR itself was touched, so entries are
+ * keyed on the MR and are only valid while its `updated_at` is unchanged.
+ */
+export class ApprovalCache {
+  static readonly MAX_ENTRIES = 500;
+
+  readonly #path: string;
+  readonly #entries: Map<string, CacheEntry>;
+
+  private constructor(path: string, entries: Map<string, CacheEntry>) {
+    this.#path = path;
+    this.#entries = entries;
+  }
+
+  /**
+   * Loads the cache from `path`. A missing or unreadable cache file is
+   * treated as an empty cache.
+   *
+   * @param {string} path - location of the cache file
+   * @returns {Promise<ApprovalCache>} the loaded cache
+   */
+  static async load(path: string): Promise<ApprovalCache> {
+    const entries = await readFile(path, { encoding: "utf8" })
+      .then((content) => new Map<string, CacheEntry>(JSON.parse(content)))
+      .catch(() => new Map<string, CacheEntry>());
+    return new ApprovalCache(path, entries);
+  }
+
+  /**
+   * Looks up cached approvals for an MR. Entries whose `updated_at` no
+   * longer matches the MR are invalidated.
+   *
+   * @param {string} key - identifies the MR, e.g. `<project_id>!<iid>`
+   * @param {string} updated_at - the MR's current `updated_at`
+   * @returns {Approvals | undefined} cached value, if still valid
+   */
+  get(key: string, updated_at: string): Approvals | undefined {
+    const entry = this.#entries.get(key);
+    if (entry === undefined) return undefined;
+
+    if (entry.updated_at !== updated_at) {
+      this.#entries.delete(key);
+      return undefined;
+    }
+
+    return {
+      approvals_left: entry.approvals_left,
+      // Entries written by older versions lack approvers.
+      pending_approvers: entry.pending_approvers ?? [],
+    };
+  }
+
+  set(key: string, updated_at: string, approvals: Approvals): void {
+    // Re-insert so that Map iteration order tracks recency.
+    this.#entries.delete(key);
+    this.#entries.set(key, { ...approvals, updated_at });
+  }
+
+  /**
+   * Writes the cache back to disk, keeping only the most recently stored
+   * `MAX_ENTRIES` entries. Failure to write is logged but not fatal.
+   */
+  async save(): Promise<void> {
+    const entries = [...this.#entries].slice(-ApprovalCache.MAX_ENTRIES);
+    await writeFile(this.#path, JSON.stringify(entries)).catch((err) =>
+      log(`Unable to write approval cache ${this.#path}: ${err.message}`)
+    );
+  }
+}
@@ -0,0 +1,93 @@
+/**
+ * Conditions that set the exit status, from highest to lowest severity. When
+ * several hold, the most severe one with a non-zero code wins.
+ */
+export const EXIT_CONDITIONS = [
+  "config_error",
+  "api_error",
+  "audit_failed",
+  "sla_violation",
+  "blocked",
+  "empty",
+] as const;
+
+export type ExitCondition = typeof EXIT_CONDITIONS[number];
+
+/**
+ * Defaults; conditions other than errors get distinct codes so scripts can
+ * tell them apart, while both kinds of error keep the long-standing status 1
+ */
+export const DEFAULT_EXIT_CODES: Readonly<Record<ExitCondition, number>> = {
+  config_error: 1,
+  api_error: 1,
+  audit_failed: 4,
+  sla_violation: 2,
+  blocked: 0,
+  empty: 3,
+};
+
+/**
+ * An error in the command line or configuration, rather than from GitLab
+ */
+export class ConfigError extends Error {
+  constructor(cause: unknown) {
+    super(cause instanceof Error ? cause.message : String(cause));
+    this.name = "ConfigError";
+  }
+}
+
+/**
+ * Collects the conditions met during a run, and keeps `process.exitCode` set
+ * to the code of the most severe one.
+ */
+export class ExitStatus {
+  #codes: Record<ExitCondition, number> = { ...DEFAULT_EXIT_CODES };
+  readonly #raised = new Set<ExitCondition>();
+
+  /**
+   * Overrides the default codes, e.g. from the `exit_codes` configuration.
+   *
+   * @param {Record<ExitCondition, number>} codes - codes to override; `0`
+   *   means the condition doesn't affect the exit status
+   */
+  configure(codes: Readonly<Record<string, number>>): void {
+    for (const [condition, code] of Object.entries(codes)) {
+      if (!EXIT_CONDITIONS.some((c) => c === condition))
+        throw new Error(
+          `unknown \`exit_codes\` condition \`${condition}\`: expected one of ${EXIT_CONDITIONS.join(", ")}`
+        );
+      if (!Number.isInteger(code) || code < 0 || code > 255)
+        throw new Error(
+          `invalid \`exit_codes\` code ${code} for \`${condition}\`: expected 0-255`
+        );
+    }
+
+    this.#codes = { ...this.#codes, ...codes };
+    process.exitCode = this.code();
+  }
+
+  /**
+   * Records that `condition` holds.
+   *
+   * @param {ExitCondition} condition - condition met
+   */
+  raise(condition: ExitCondition): void {
+    this.#raised.add(condition);
+    process.exitCode = this.code();
+  }
+
+  /**
+   * Finds the exit code for the most severe condition raised.
+   *
+   * @returns {number} exit code; `0` if no enabled condition was raised
+   */
+  code(): number {
+    const condition = EXIT_CONDITIONS.find(
+      (c) => this.#raised.has(c) && this.#codes[c] !== 0
+    );
+    return condition ? this.#codes[condition] : 0;
+  }
+}
+
+/** Exit status shared by the whole run */
+export const exitStatus = new ExitStatus();
@@ -0,0 +1,436 @@
+import { once } from "events";
+import { Writable } from "stream";
+
+import { BlockerKind, Label, MergeRequest, SimpleMergeRequest } from "./gitlab";
+import { formatDuration, formatTimestamp, parseChoice } from "./util";
+
+export const FORMATS = ["markdown", "gha", "json", "org", "toml"] as const;
+
+export type Format = typeof FORMATS[number];
+
+export const SECTIONS = ["ready", "blocked"] as const;
+
+export type Section = typeof SECTIONS[number];
+
+export const SECTION_HEADERS: Readonly<Record<Section, string>> = {
+  ready: "Ready to Merge",
+  blocked: "Blocked",
+};
+
+/**
+ * A renderer-agnostic snapshot of the report, split into its sections
+ */
+export interface Report {
+  readonly generated_at: string;
+  readonly target_branch: string;
+  readonly ready: ReadonlyArray<MergeRequest>;
+  readonly blocked: ReadonlyArray<MergeRequest>;
+}
+
+/**
+ * Builds a `Report` by partitioning MRs on whether they have blockers.
+ *
+ * @param {string} target_branch - branch the MRs target
+ * @param {Array<MergeRequest>} mrs - MRs to report
+ * @returns {Report} the report
+ */
+export function buildReport(
+  target_branch: string,
+  mrs: ReadonlyArray<MergeRequest>
+): Report {
+  return {
+    generated_at: new Date().toISOString(),
+    target_branch,
+    ready: mrs.filter((mr) => mr.blockers.length === 0),
+    blocked: mrs.filter((mr) => mr.blockers.length > 0),
+  };
+}
+
+/**
+ * Parses a `section_order`, adding any sections it leaves out at the end so
+ * that no MRs go unreported.
+ *
+ * @param {Array<string>} order - user-supplied section names
+ * @returns {Array<Section>} every section, in order
+ */
+export function parseSectionOrder(order: ReadonlyArray<string>): Array<Section> {
+  const sections = order.map((s) => parseChoice(SECTIONS, s, "section"));
+  return [...new Set([...sections, ...SECTIONS])];
+}
+
+/**
+ * Formats a `Report` as JSON, including each MR's blockers and notes.
+ *
+ * @param {Report} report - report to display
+ * @param {boolean} compact - if true, omits the whitespace used to make the
+ *   JSON readable
+ * @returns {string} JSON text
+ */
+export function formatJson(report: Report, compact = false): string {
+  return `${JSON.stringify(report, null, compact ? undefined : 2)}\n`;
+}
+
+/**
+ * Writes output to a stream as it's generated, rather than building it all in
+ * memory first. Waits for the stream to drain whenever its buffer is full.
+ *
+ * @param {Writable} out - stream to write to, e.g. `stdout`
+ * @param {Iterable<string>} chunks - output, e.g. from one of the `render*`
+ *   functions
+ */
+export async function writeChunks(
+  out: Writable,
+  chunks: Iterable<string>
+): Promise<void> {
+  for (const chunk of chunks) {
+    if (!out.write(chunk)) await once(out, "drain");
+  }
+}
+
+function formatLabel(
+  label: string,
+  label_emoji: Readonly<Record<string, string>>
+): string {
+  const emoji = label_emoji[label];
+  return emoji ? `${emoji} ${label}` : label;
+}
+
+// Only plain colors, like `#428BCA`, are safe to put in a style attribute.
+const LABEL_COLOR = /^#[0-9a-f]{3,8}$/i;
+
+function labelDetails(mr: MergeRequest): ReadonlyArray<Label> {
+  return mr.label_details ?? mr.labels.map((name) => ({ name }));
+}
+
+/**
+ * Formats a label as an HTML chip in the label's colors, with its description
+ * as a tooltip, when GitLab provided them.
+ *
+ * @param {Label} label - label to display
+ * @param {Record<string, string>} label_emoji - emoji to prefix labels with
+ * @returns {string} HTML text
+ */
+function formatHtmlLabel(
+  label: Label,
+  label_emoji: Readonly<Record<string, string>>
+): string {
+  const { color, text_color, description } = label;
+  const style = [
+    color && LABEL_COLOR.test(color) && `background-color: ${color}; padding: 0 0.4em`,
+    text_color && LABEL_COLOR.test(text_color) && `color: ${text_color}`,
+  ]
+    .filter(Boolean)
+    .join("; ");
+  const attributes = [
+    style && ` style="${style}"`,
+    description && ` title="${escapeHtml(description)}"`,
+  ]
+    .filter(Boolean)
+    .join("");
+
+  return `<span${attributes}>${escapeHtml(formatLabel(label.name, label_emoji))}</span>`;
+}
+
+/**
+ * Formats a `MergeRequest` for display in Slack-style markdown.
+ *
+ * @param {string} header - used as section header
+ * @param {Array<MergeRequest>} mrs - list of MRs to display
+ * @param {Record<string, string>} label_emoji - emoji to prefix labels with
+ * @returns {string} Slack-formatted text
+ */
+export function formatMRs(
+  header: string,
+  mrs: ReadonlyArray<MergeRequest>,
+  label_emoji: Readonly<Record<string, string>> = {}
+): string {
+  return [...renderMRs(header, mrs, label_emoji)].join("");
+}
+
+/**
+ * Like `formatMRs`, but generates the text a line at a time.
+ *
+ * @param {string} header - used as section header
+ * @param {Array<MergeRequest>} mrs - list of MRs to display
+ * @param {Record<string, string>} label_emoji - emoji to prefix labels with
+ * @returns {Iterable<string>} lines of Slack-formatted text
+ */
+export function* renderMRs(
+  header: string,
+  mrs: ReadonlyArray<MergeRequest>,
+  label_emoji: Readonly<Record<string, string>> = {}
+): Iterable<string> {
+  yield `* *${header}*\n`;
+
+  for (const mr of mrs) {
+    yield `    * [${mr.title}](${mr.web_url}) (${mr.author.username})\n`;
+    yield `        * \`${mr.source_branch}\` → \`${mr.target_branch}\`\n`;
+
+    if (mr.labels.length > 0) {
+      const labels = mr.labels.map((label) => formatLabel(label, label_emoji));
+      yield `        * Labels: ${labels.join(", ")}\n`;
+    }
+
+    if (mr.blockers.length > 0) {
+      const blockers = mr.blockers.map((blocker) => blocker.message);
+      yield `        * ${blockers.join(", ")}\n`;
+    }
+
+    if (mr.notes.length > 0) {
+      yield `        * _${mr.notes.join(", ")}_\n`;
+    }
+  }
+}
+
+/**
+ * Formats all the details of a single `MergeRequest`, including blockers.
+ *
+ * @param {MergeRequest} mr - MR to display
+ * @param {Record<string, string>} label_emoji - emoji to prefix labels with
+ * @param {string} timezone - IANA timezone to show timestamps in
+ * @returns {string} plain text description of the MR
+ */
+export function formatDetail(
+  mr: MergeRequest,
+  label_emoji: Readonly<Record<string, string>> = {},
+  timezone = "UTC"
+): string {
+  const age = (timestamp: string) =>
+    `${formatTimestamp(timestamp, timezone)} (${formatDuration(
+      Date.now() - Date.parse(timestamp)
+    )} ago)`;
+  const blockers = mr.blockers.map((b) => b.message);
+
+  const fields: Array<[string, string]> = [
+    ["Author", `${mr.author.name} (@${mr.author.username})`],
+    ["Branch", `${mr.source_branch} → ${mr.target_branch}`],
+    ["State", mr.state],
+    ["Created", age(mr.created_at)],
+    ["Updated", age(mr.updated_at)],
+    [
+      "Labels",
+      labelDetails(mr)
+        .map(({ name, description }) =>
+          description
+            ? `${formatLabel(name, label_emoji)} (${description})`
+            : formatLabel(name, label_emoji)
+        )
+        .join(", "),
+    ],
+    ["Blockers", blockers.length > 0 ? blockers.join(", ") : "none; ready to merge"],
+    ["Notes", mr.notes.join(", ")],
+  ];
+
+  return [
+    `!${mr.iid} ${mr.title}\n`,
+    `${mr.web_url}\n\n`,
+    ...fields
+      .filter(([, value]) => value)
+      .map(([name, value]) => `${`${name}:`.padEnd(10)}${value}\n`),
+  ].join("");
+}
+
+/**
+ * Formats a `SimpleMergeRequest` for display in Slack-style markdown.
+ * Simple MRs carry no author or blocker data, so only links are shown.
+ *
+ * @param {string} header - used as section header
+ * @param {Array<SimpleMergeRequest>} mrs - list of MRs to display
+ * @returns {string} Slack-formatted text
+ */
+export function formatSimpleMRs(
+  header: string,
+  mrs: ReadonlyArray<SimpleMergeRequest>
+): string {
+  return [...renderSimpleMRs(header, mrs)].join("");
+}
+
+/**
+ * Like `formatSimpleMRs`, but generates the text a line at a time.
+ *
+ * @param {string} header - used as section header
+ * @param {Array<SimpleMergeRequest>} mrs - list of MRs to display
+ * @returns {Iterable<string>} lines of Slack-formatted text
+ */
+export function* renderSimpleMRs(
+  header: string,
+  mrs: ReadonlyArray<SimpleMergeRequest>
+): Iterable<string> {
+  yield `* *${header}*\n`;
+
+  for (const mr of mrs) {
+    yield `    * [${mr.title}](${mr.web_url})\n`;
+  }
+}
+
+/**
+ * Formats a `Report` as a standalone HTML page.
+ *
+ * @param {Report} report - report to display
+ * @param {Record<string, string>} label_emoji - emoji to prefix labels with
+ * @param {string} timezone - IANA timezone to show timestamps in
+ * @param {Array<Section>} order - order to show the sections in
+ * @returns {string} HTML document
+ */
+export function formatHtml(
+  report: Report,
+  label_emoji: Readonly<Record<string, string>> = {},
+  timezone = "UTC",
+  order: ReadonlyArray<Section> = SECTIONS
+): string {
+  const section = (header: string, mrs: ReadonlyArray<MergeRequest>) => {
+    if (mrs.length === 0) return "";
+
+    const items = mrs.map((mr) => {
+      const labels = labelDetails(mr).map((l) => formatHtmlLabel(l, label_emoji));
+      const details = [
+        escapeHtml(`${mr.source_branch} → ${mr.target_branch}`),
+        labels.length > 0 && `Labels: ${labels.join(", ")}`,
+        mr.blockers.length > 0 &&
+          escapeHtml(mr.blockers.map((b) => b.message).join(", ")),
+      ]
+        .filter((detail): detail is string => Boolean(detail))
+        .map((detail) => `<li>${detail}</li>`);
+      if (mr.notes.length > 0)
+        details.push(`<li><em>${escapeHtml(mr.notes.join(", "))}</em></li>`);
+
+      return [
+        `<li><a href="${escapeHtml(mr.web_url)}">${escapeHtml(mr.title)}</a>`,
+        ` (${escapeHtml(mr.author.username)})`,
+        details.length > 0 ? `<ul>${details.join("")}</ul>` : "",
+        "</li>\n",
+      ].join("");
+    });
+
+    return `<h2>${escapeHtml(header)}</h2>\n<ul>\n${items.join("")}</ul>\n`;
+  };
+
+  const branch = escapeHtml(report.target_branch);
+
+  return `<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Open MRs against ${branch}</title>
+</head>
+<body>
+<h1>Open MRs against <code>${branch}</code></h1>
+${order.map((s) => section(SECTION_HEADERS[s], report[s])).join("")}<p><small>Generated ${escapeHtml(formatTimestamp(report.generated_at, timezone))}</small></p>
+</body>
+</html>
+`;
+}
+
+function escapeHtml(s: string): string {
+  return s
+    .replace(/&/g, "&amp;")
+    .replace(/</g, "&lt;")
+    .replace(/>/g, "&gt;")
+    .replace(/"/g, "&quot;")
+    .replace(/'/g, "&#39;");
+}
+
+/**
+ * Formats a `Report` as an Emacs Org-mode outline, with each MR's details in
+ * a property drawer.
+ *
+ * @param {Report} report - report to display
+ * @param {number} level - heading level of the report's top heading, for
+ *   nesting the report in an existing Org file
+ * @param {Array<Section>} order - order to show the sections in
+ * @returns {string} Org-mode text
+ */
+export function formatOrg(
+  report: Report,
+  level = 1,
+  order: ReadonlyArray<Section> = SECTIONS
+): string {
+  return [...renderOrg(report, level, order)].join("");
+}
+
+/**
+ * Like `formatOrg`, but generates the outline a line at a time.
+ *
+ * @param {Report} report - report to display
+ * @param {number} level - heading level of the report's top heading
+ * @param {Array<Section>} order - order to show the sections in
+ * @returns {Iterable<string>} lines of Org-mode text
+ */
+export function* renderOrg(
+  report: Report,
+  level = 1,
+  order: ReadonlyArray<Section> = SECTIONS
+): Iterable<string> {
+  const stars = (depth: number) => "*".repeat(level + depth);
+  yield `${stars(0)} Open MRs against =${report.target_branch}=\n`;
+
+  for (const section of order) {
+    const mrs = report[section];
+    if (mrs.length === 0) continue;
+    yield `${stars(1)} ${SECTION_HEADERS[section]}\n`;
+
+    for (const mr of mrs) {
+      // Brackets in the description would end the link early.
+      const title = mr.title.replace(/\[/g, "{").replace(/\]/g, "}");
+      const properties = [
+        ["AUTHOR", mr.author.username],
+        ["BRANCH", `${mr.source_branch} → ${mr.target_branch}`],
+        ["LABELS", mr.labels.join(", ")],
+        ["BLOCKERS", mr.blockers.map((b) => b.message).join(", ")],
+        ["NOTES", mr.notes.join(", ")],
+      ].filter(([, value]) => value);
+
+      yield `${stars(2)} [[${mr.web_url}][${title}]]\n`;
+      yield ":PROPERTIES:\n";
+      for (const [name, value] of properties) {
+        yield `:${name}: ${value}\n`;
+      }
+      yield ":END:\n";
+    }
+  }
+}
+
+// Blockers that can only be cleared by changing the MR's code.
+const ERROR_BLOCKERS: ReadonlyArray<BlockerKind> = ["conflicts", "unmergeable"];
+
+/**
+ * Formats blocked MRs as GitHub Actions workflow commands, so that they are
+ * shown as annotations in the workflow run summary.
+ *
+ * @param {Array<MergeRequest>} mrs - list of MRs; unblocked MRs are skipped
+ * @returns {string} one `::warning` or `::error` command per blocked MR
+ */
+export function formatAnnotations(mrs: ReadonlyArray<MergeRequest>): string {
+  return [...renderAnnotations(mrs)].join("");
+}
+
+/**
+ * Like `formatAnnotations`, but generates the commands one at a time.
+ *
+ * @param {Array<MergeRequest>} mrs - list of MRs; unblocked MRs are skipped
+ * @returns {Iterable<string>} one `::warning` or `::error` command per blocked MR
+ */
+export function* renderAnnotations(
+  mrs: ReadonlyArray<MergeRequest>
+): Iterable<string> {
+  for (const mr of mrs) {
+    if (mr.blockers.length === 0) continue;
+
+    const level = mr.blockers.some((b) => ERROR_BLOCKERS.includes(b.kind))
+      ? "error"
+      : "warning";
+    const blockers = mr.blockers.map((blocker) => blocker.message);
+    const message = `!${mr.iid} ${mr.title} (${mr.author.username}): ${blockers.join(", ")}`;
+
+    yield `::${level} title=${escapeProperty("Blocked MR")}::${escapeData(message)}\n`;
+  }
+}
+
+// https://github.com/actions/toolkit/blob/main/packages/core/src/command.ts
+function escapeData(s: string): string {
+  return s.replace(/%/g, "%25").replace(/\r/g, "%0D").replace(/\n/g, "%0A");
+}
+
+function escapeProperty(s: string): string {
+  return escapeData(s).replace(/:/g, "%3A").replace(/,/g, "%2C");
+}
@@ -1,7 +1,10 @@
-import { IncomingMessage } from 'http';
+import { IncomingHttpHeaders, IncomingMessage } from 'http';
 import { RequestOptions, get as httpsGet } from 'https';
 
-import { log } from './util';
+import { ApprovalCache, Approvals } from './cache';
+import { runBlockerHook } from './hook';
+import { profiler } from './profile';
+import { formatDuration, log, mapLimit, parseChoice } from './util';
 
 /**
  * GitLab API types
@@ -10,59 +13,323 @@ import { log } from './util';
  *
  * https://docs.gitlab.com/ee/api/merge_requests.html#list-project-merge-requests
  * https://docs.gitlab.com/ee/api/merge_request_approvals.html#get-configuration-1
+ * https://docs.gitlab.com/ee/api/merge_request_approvals.html#get-project-level-rules
+ * https://docs.gitlab.com/ee/api/merge_request_approvals.html#get-the-approval-state-of-merge-requests
  */
 
 /*
  * Type aliases
  */
 
-export type MRState = "closed" | "merged" | "open";
+export const MR_STATES = ["opened", "closed", "locked", "merged"] as const;
 
-export type MergeStatus =
-  | "unchecked"
-  | "checking"
-  | "can_be_merged"
-  | "cannot_be_merged"
-  | "cannot_be_merged_recheck";
+export type MRState = typeof MR_STATES[number];
+
+export const MERGE_STATUSES = [
+  "unchecked",
+  "checking",
+  "can_be_merged",
+  "cannot_be_merged",
+  "cannot_be_merged_recheck",
+] as const;
+
+export type MergeStatus = typeof MERGE_STATUSES[number];
+
+/**
+ * Parses an `MRState`, as spelled by the GitLab API (e.g. `opened`).
+ *
+ * @param {string} s - user-supplied state
+ * @returns {MRState} the state
+ */
+export function parseMRState(s: string): MRState {
+  return parseChoice(MR_STATES, s, "MR state");
+}
+
+/**
+ * Parses a `MergeStatus`, as spelled by the GitLab API (e.g. `can_be_merged`).
+ *
+ * @param {string} s - user-supplied merge status
+ * @returns {MergeStatus} the merge status
+ */
+export function parseMergeStatus(s: string): MergeStatus {
+  return parseChoice(MERGE_STATUSES, s, "merge status");
+}
+
+export const BLOCKER_KINDS = [
+  "threads",
+  "conflicts",
+  "unmergeable",
+  "approval",
+  "pipeline",
+  "job",
+  "issue",
+  "hook",
+] as const;
+
+export type BlockerKind = typeof BLOCKER_KINDS[number];
+
+/*
+ * Errors
+ */
+
+/**
+ * An unsuccessful (non-2xx) API response
+ */
+export class HttpError extends Error {
+  constructor(readonly statusCode: number, readonly uri: string) {
+    super(`${uri} - statusCode=${statusCode}`);
+    this.name = "HttpError";
+  }
+}
 
 /*
  * Interfaces
  */
 
-interface Author {
+export interface Blocker {
+  readonly kind: BlockerKind;
+  readonly message: string;
+}
+
+export interface Author {
   readonly id: number;
   readonly name: string;
   readonly username: string;
 }
 
-interface MRApprovalStatus {
+interface AwardEmoji {
+  readonly id: number;
+  readonly name: string;
+  readonly user: Author;
+}
+
+interface ApiResponse<T> {
+  readonly data: T;
+  readonly headers: IncomingHttpHeaders;
+}
+
+interface Pipeline {
+  readonly id: number;
+  readonly status: string;
+  readonly web_url: string;
+}
+
+/**
+ * A label with its details, as returned with `with_labels_details`
+ */
+export interface Label {
+  readonly name: string;
+  readonly color?: string;
+  readonly text_color?: string;
+  readonly description?: string | null;
+}
+
+interface Issue {
+  readonly id: number;
+  readonly iid: number;
+}
+
+interface Job {
+  readonly id: number;
+  readonly name: string;
+  readonly status: string;
+}
+
+interface Project {
+  readonly id: number;
+  readonly path_with_namespace: string;
+}
+
+interface Note {
+  readonly id: number;
+  readonly body: string;
+  readonly system: boolean;
+  readonly created_at: string;
+  readonly updated_at: string;
+}
+
+export interface MRApprovalStatus {
   readonly approvals_required: number;
-  readonly approvals_left: number;
+  readonly approvals_left: number | null;
+  readonly approved_by: ReadonlyArray<{ readonly user: Author }>;
   readonly id: number;
   readonly iid: number;
   readonly project_id: number;
+  readonly suggested_approvers: ReadonlyArray<Author>;
+  readonly title: string;
+}
+
+/**
+ * A project-level approval rule
+ */
+export interface ApprovalRule {
+  readonly id: number;
+  readonly name: string;
+  readonly rule_type: string;
+  readonly approvals_required: number;
+  readonly eligible_approvers?: ReadonlyArray<Author>;
+}
+
+/**
+ * An approval rule as it applies to a single MR
+ */
+interface MRApprovalRule extends ApprovalRule {
+  readonly approved: boolean;
+  readonly approved_by?: ReadonlyArray<Author>;
+}
+
+interface MRApprovalState {
+  readonly rules: ReadonlyArray<MRApprovalRule>;
+}
+
+/**
+ * The trimmed MR representation returned with `view=simple`
+ */
+export interface SimpleMergeRequest {
+  readonly created_at: string;
+  readonly iid: number;
+  readonly state: MRState;
   readonly title: string;
+  readonly updated_at: string;
+  readonly web_url: string;
+}
+
+/**
+ * The fields of a merged MR needed to chart merges over time
+ */
+export interface MergedMergeRequest extends SimpleMergeRequest {
+  readonly author: Author;
+  readonly merged_at: string | null;
 }
 
-export interface MergeRequest {
+export interface MergeRequest extends SimpleMergeRequest {
   approvals_needed: number;
   readonly author: Author;
-  blockers: Array<string>;
+  blockers: Array<Blocker>;
   readonly blocking_discussions_resolved: boolean;
-  readonly draft: boolean;
+  readonly description?: string | null;
+  /** Normalized by `#inspect` from whichever draft fields GitLab provides */
+  draft?: boolean;
   readonly has_conflicts: boolean;
-  readonly iid: number;
-  readonly labels: Array<string>;
+  readonly head_pipeline?: Pipeline | null;
+  informal_approvals?: number;
+  /** Label names, even with `label_details` */
+  labels: Array<string>;
+  /** Labels including their color and description; details need `label_details` */
+  label_details?: Array<Label>;
+  last_activity_at?: string;
   readonly merge_status: MergeStatus;
+  metadata?: Record<string, string>;
+  notes: Array<string>;
+  pending_approvers?: ReadonlyArray<Author>;
+  pipeline?: Pipeline;
+  review_rounds?: number;
+  readonly reviewers?: ReadonlyArray<Author>;
   readonly source_branch: string;
-  readonly state: MRState;
-  readonly title: string;
-  readonly web_url: string;
-  readonly work_in_progress: boolean;
+  readonly target_branch: string;
+  readonly work_in_progress?: boolean;
+}
+
+// Pipeline statuses that don't block merging.
+const PASSED_PIPELINE = ["success", "skipped", "manual"];
+
+// Title prefixes GitLab recognizes as marking an MR as a draft.
+const DRAFT_TITLE = /^\s*(\[(draft|wip)\]|\((draft|wip)\)|(draft|wip):)/i;
+
+/**
+ * Determines whether an MR is a draft.
+ *
+ * GitLab keeps `draft` and the deprecated `work_in_progress` in sync, but
+ * older instances (and older data) may only have one, or disagree. In order
+ * of precedence, this uses `draft`, then `work_in_progress`, and finally falls
+ * back to checking the title for a draft prefix like `Draft:`.
+ *
+ * @param {MergeRequest} mr - MR to check
+ * @returns {boolean} true if the MR is a draft
+ */
+export function isDraft(mr: MergeRequest): boolean {
+  return mr.draft ?? mr.work_in_progress ?? DRAFT_TITLE.test(mr.title);
+}
+
+// A `---`-fenced block at the very start of a description.
+const FRONTMATTER = /^\s*---\r?\n([\s\S]*?)\r?\n---[ \t]*(\r?\n|$)/;
+
+/**
+ * Extracts the given keys from the YAML-style frontmatter at the start of an
+ * MR description, e.g.:
+ *
+ * ```
+ * ---
+ * risk: high
+ * ---
+ * ```
+ *
+ * Only simple `key: value` lines are understood; nested YAML is ignored.
+ *
+ * @param {string} description - MR description, if any
+ * @param {Array<string>} keys - keys to extract
+ * @returns {Record<string, string>} values found for `keys`; empty if the
+ *   description has no frontmatter
+ */
+export function parseFrontmatter(
+  description: string | null | undefined,
+  keys: ReadonlyArray<string>
+): Record<string, string> {
+  const metadata: Record<string, string> = {};
+  const [, frontmatter] = description?.match(FRONTMATTER) ?? [];
+  if (!frontmatter) return metadata;
+
+  for (const line of frontmatter.split(/\r?\n/)) {
+    const [, key, value] = /^([\w-]+)[ \t]*:[ \t]*(.*?)\s*$/.exec(line) ?? [];
+    if (key && value && keys.includes(key))
+      metadata[key] = value.replace(/^(["'])(.*)\1$/, "$2");
+  }
+
+  return metadata;
+}
+
+/**
+ * Extracts the approval data mrstat needs from an MR's `/approvals` response.
+ *
+ * `approvals_left` is `null` on some instances, e.g. when rules are
+ * configured but not yet computed; it's kept as `null` for "unknown" rather
+ * than treated as an error.
+ *
+ * @param {MRApprovalStatus} status - the `/approvals` response
+ * @returns {Approvals} approvals left and approvers who haven't approved yet
+ */
+export function parseApprovals(status: MRApprovalStatus): Approvals {
+  const approved = (status.approved_by ?? []).map(({ user }) => user.id);
+  return {
+    approvals_left: status.approvals_left ?? null,
+    pending_approvers: (status.suggested_approvers ?? [])
+      .filter(({ id }) => !approved.includes(id))
+      .map(({ id, name, username }) => ({ id, name, username })),
+  };
 }
 
 export interface GitLabConfig {
-  readonly api_token: string;
+  readonly active_author_ids?: Array<number>;
+  readonly api_token?: string;
+  readonly approval_emoji?: string;
+  readonly approval_rule_summary?: boolean;
+  readonly approvals_advisory?: boolean;
+  readonly blocker_hook_command?: string;
+  readonly check_approval_rules?: boolean;
+  readonly check_pipelines?: boolean;
+  readonly count_review_rounds?: boolean;
+  readonly description_keys?: Array<string>;
+  readonly issues_advisory?: boolean;
+  readonly label_details?: boolean;
+  readonly labels?: string;
+  readonly max_response_bytes?: number;
+  readonly merge_status_recheck?: boolean;
+  readonly ready_reactions?: number;
+  readonly required_job?: string;
+  readonly requires_issue?: boolean;
+  readonly retry_on_status?: Array<number>;
+  readonly reviewer_usernames?: Array<string>;
+  readonly show_last_activity?: boolean;
   readonly target_branch?: string;
   readonly authors: Record<string, number>;
   readonly project_id: number;
@@ -70,22 +337,80 @@ export interface GitLabConfig {
 
 export class GitLab implements Omit<GitLabConfig, "api_token"> {
   static readonly GITLAB_API_BASE = "https://gitlab.com/api/v4";
+  static readonly MAX_RESPONSE_BYTES = 32 * 1024 * 1024;
+  static readonly MAX_ATTEMPTS = 3;
+  static readonly MAX_CONCURRENT_MRS = 10;
+  static readonly MAX_CONCURRENT_PAGES = 4;
+  static readonly PER_PAGE = 100;
+  static readonly RETRY_ON_STATUS = [500, 502, 503, 504, 429];
+  // Network errors that are likely to succeed if retried.
+  static readonly RETRY_ON_ERROR = ["ECONNRESET", "ETIMEDOUT", "EAI_AGAIN"];
 
-  readonly #api_token: string;
+  readonly #api_token?: string;
+  readonly active_author_ids?: Array<number>;
+  readonly approval_emoji?: string;
+  readonly approval_rule_summary: boolean;
+  readonly approvals_advisory: boolean;
   readonly authors: Record<string, number>;
+  readonly blocker_hook_command?: string;
+  readonly check_approval_rules: boolean;
+  readonly check_pipelines: boolean;
+  readonly count_review_rounds: boolean;
+  readonly description_keys: Array<string>;
+  readonly issues_advisory: boolean;
+  readonly label_details: boolean;
+  readonly labels?: string;
+  readonly max_response_bytes: number;
+  readonly merge_status_recheck: boolean;
   readonly project_id: number;
+  readonly ready_reactions?: number;
+  readonly required_job?: string;
+  readonly requires_issue: boolean;
+  readonly retry_on_status: Array<number>;
+  readonly reviewer_usernames?: Array<string>;
+  readonly show_last_activity: boolean;
   readonly target_branch: string;
 
   constructor(config: GitLabConfig) {
     // Ensure configuration includes required fields
-    if (!config?.api_token)
+    if (!config?.project_id)
       throw new Error(
-        `missing \`api_token\`: see https://docs.gitlab.com/ee/user/profile/personal_access_tokens.html`
+        `missing \`project_id\`: you can find Project ID in your project settings`
       );
 
-    if (!config?.project_id)
+    // Client errors won't go away when retried, except for timeouts and rate limits.
+    const pointless = config?.retry_on_status?.filter(
+      (status) =>
+        !Number.isInteger(status) ||
+        status < 400 ||
+        status > 599 ||
+        (status < 500 && status !== 408 && status !== 429)
+    );
+    if (pointless && pointless.length > 0)
       throw new Error(
-        `missing \`project_id\`: you can find Project ID in your project settings`
+        `invalid \`retry_on_status\` ${pointless.join(", ")}: only 408, 429 and 5xx statuses may be retried`
+      );
+
+    const ready_reactions = config?.ready_reactions;
+    if (
+      ready_reactions !== undefined &&
+      (!Number.isInteger(ready_reactions) || ready_reactions < 1)
+    )
+      throw new Error(
+        `invalid \`ready_reactions\` ${ready_reactions}: expected a positive integer`
+      );
+    if (ready_reactions !== undefined && !config.approval_emoji)
+      throw new Error(
+        `\`ready_reactions\` requires \`approval_emoji\`: the reaction to count`
+      );
+
+    const max_response_bytes = config?.max_response_bytes;
+    if (
+      max_response_bytes !== undefined &&
+      (!Number.isInteger(max_response_bytes) || max_response_bytes < 1)
+    )
+      throw new Error(
+        `invalid \`max_response_bytes\` ${max_response_bytes}: expected a positive integer`
       );
 
     if (!config?.authors || Object.entries(config.authors).length < 1) {
@@ -93,51 +418,654 @@ export class GitLab implements Omit<GitLabConfig, "api_token"> {
       console.warn("all open project MRs will be returned");
     }
 
+    if (!config?.api_token) {
+      console.warn(`missing \`api_token\`; only public projects are accessible`);
+    }
+
     if (!config?.target_branch) {
       console.warn(`Configuration missing branch name; defaulting to main`);
     }
 
-    this.#api_token = config.api_token;
+    this.#api_token = config.api_token || undefined;
+    this.active_author_ids = config.active_author_ids;
+    this.approval_emoji = config.approval_emoji;
+    this.approval_rule_summary = config.approval_rule_summary ?? false;
+    this.approvals_advisory = config.approvals_advisory ?? false;
     this.authors = config.authors ?? [];
+    this.blocker_hook_command = config.blocker_hook_command;
+    this.check_approval_rules = config.check_approval_rules ?? false;
+    this.check_pipelines = config.check_pipelines ?? false;
+    this.count_review_rounds = config.count_review_rounds ?? false;
+    this.description_keys = config.description_keys ?? [];
+    this.issues_advisory = config.issues_advisory ?? false;
+    this.label_details = config.label_details ?? false;
+    this.labels = config.labels;
+    this.max_response_bytes =
+      config.max_response_bytes ?? GitLab.MAX_RESPONSE_BYTES;
+    this.merge_status_recheck = config.merge_status_recheck ?? false;
     this.project_id = config.project_id;
+    this.ready_reactions = config.ready_reactions;
+    this.required_job = config.required_job;
+    this.requires_issue = config.requires_issue ?? false;
+    this.retry_on_status = config.retry_on_status ?? GitLab.RETRY_ON_STATUS;
+    this.reviewer_usernames = config.reviewer_usernames;
+    this.show_last_activity = config.show_last_activity ?? false;
     this.target_branch = config?.target_branch ?? "main";
   }
 
   /**
    * Queries GitLab for open MRs on the given branch.
-   * If authors were provided, restricts MRs authored by those users.
+   * If authors were provided, restricts MRs authored by those users, and if
+   * reviewer usernames were provided, to MRs any of them is reviewing.
    *
+   * @param {ApprovalCache} cache - optional cache of approval data; MRs whose
+   *   `updated_at` is unchanged since they were cached skip the approvals request
    * @returns {MergeRequest[]} Array of all matching MRs
    */
-  async openMergeRequests(): Promise<Array<MergeRequest>> {
-    const all_mrs = await this.#get<MergeRequest[]>("/merge_requests", {
+  async openMergeRequests(cache?: ApprovalCache): Promise<Array<MergeRequest>> {
+    const all_mrs = await this.#listMergeRequests<MergeRequest>({
       scope: "all",
       state: "opened",
       target_branch: this.target_branch,
+      ...this.#labelParams(),
+      ...(this.label_details ? { with_labels_details: "true" } : {}),
+      // Ask GitLab to recompute stale `unchecked`/`checking` merge statuses.
+      ...(this.merge_status_recheck ? { with_merge_status_recheck: "true" } : {}),
     });
 
     const author_ids = Object.values(this.authors);
-    const mrs =
-      author_ids.length > 0
-        ? all_mrs.filter((mr: MergeRequest) =>
-            author_ids.includes(mr.author.id)
-          )
-        : all_mrs;
-
-    // GitLab API is slow (~1-2s/req), so parallelize
-    // the secondary requests for approval data.
-    await Promise.all(
-      mrs.map(async (mr: MergeRequest) =>
-        this.#get<MRApprovalStatus>(`/merge_requests/${mr.iid}/approvals`).then(
-          (approvals) => {
-            mr.approvals_needed = approvals.approvals_left;
-            mr.blockers = GitLab.#findBlockers(mr);
-          }
+    const reviewers = this.reviewer_usernames;
+    const mrs = all_mrs
+      .filter((mr) => author_ids.length < 1 || author_ids.includes(mr.author.id))
+      .filter(
+        (mr) =>
+          !reviewers ||
+          (mr.reviewers ?? []).some(({ username }) => reviewers.includes(username))
+      );
+
+    // GitLab API is slow (~1-2s/req), so parallelize the secondary
+    // requests for each MR, capped to avoid hammering the server.
+    await mapLimit(mrs, GitLab.MAX_CONCURRENT_MRS, (mr) =>
+      profiler.time("inspect", () => this.#inspect(mr, cache), { iid: mr.iid })
+    );
+
+    GitLab.#addNotes(mrs);
+
+    return mrs;
+  }
+
+  /**
+   * Fetches a single MR, including its approval data and blockers.
+   *
+   * @param {number} iid - the MR's project-level id (as in `!42`)
+   * @param {ApprovalCache} cache - optional cache of approval data
+   * @returns {Promise<MergeRequest>} the MR
+   */
+  async mergeRequest(iid: number, cache?: ApprovalCache): Promise<MergeRequest> {
+    const mr = await this.#get<MergeRequest>(`/merge_requests/${iid}`).catch(
+      (e) => {
+        throw e instanceof HttpError && e.statusCode === 404
+          ? new Error(`MR !${iid} not found in project id ${this.project_id}`)
+          : e;
+      }
+    );
+
+    // Only the MR list can include label details, so look the MR up there too.
+    if (this.label_details) {
+      const [detailed] = await this.#get<MergeRequest[]>("/merge_requests", {
+        "iids[]": iid.toString(),
+        with_labels_details: "true",
+      });
+      if (detailed) mr.labels = detailed.labels;
+    }
+
+    await profiler.time("inspect", () => this.#inspect(mr, cache), { iid });
+    return mr;
+  }
+
+  /**
+   * Fetches the project's approval rules, including who is eligible to
+   * approve under each.
+   *
+   * The rules endpoint also 404s for projects that exist but whose edition or
+   * tier has no approval rules, so the project itself is checked before
+   * blaming `project_id`.
+   *
+   * @returns {Promise<ApprovalRule[]>} the project's approval rules
+   */
+  async approvalRules(): Promise<Array<ApprovalRule>> {
+    try {
+      return await this.#getAllPages<ApprovalRule>("/approval_rules", {});
+    } catch (e) {
+      if (!(e instanceof HttpError && e.statusCode === 404)) throw e;
+
+      const project_found = await this.#get<Project>("").then(
+        () => true,
+        (err) => {
+          if (err instanceof HttpError && err.statusCode === 404) return false;
+          throw err;
+        }
+      );
+      if (!project_found) throw await this.#projectNotFound();
+      throw new Error(
+        `approval rules aren't available for project id ${this.project_id}—` +
+          "they need an edition or tier of GitLab that supports them"
+      );
+    }
+  }
+
+  /**
+   * Fetches the secondary data for an MR and derives its blockers and notes.
+   *
+   * @param {MergeRequest} mr - MR to inspect; updated in place
+   * @param {ApprovalCache} cache - optional approval cache
+   */
+  async #inspect(mr: MergeRequest, cache?: ApprovalCache): Promise<void> {
+    // Older instances only send `work_in_progress`, or nothing but the title.
+    mr.draft = isDraft(mr);
+
+    // With `with_labels_details`, GitLab sends label objects instead of names.
+    const labels: ReadonlyArray<string | Label> = mr.labels;
+    mr.label_details = labels.map((l) => (typeof l === "string" ? { name: l } : l));
+    mr.labels = mr.label_details.map((l) => l.name);
+
+    const [
+      approvals,
+      informal_approvals,
+      last_activity_at,
+      review_rounds,
+      pipeline,
+      has_issue,
+      approval_rules,
+    ] = await Promise.all([
+      this.#approvals(mr, cache),
+      this.#informalApprovals(mr),
+      this.#lastActivity(mr),
+      this.#reviewRounds(mr),
+      this.#pipeline(mr),
+      this.#hasLinkedIssue(mr),
+      this.#approvalRules(mr),
+    ]);
+    const required_job_passed = await this.#requiredJobPassed(pipeline);
+    // Unknown approvals are noted below rather than treated as blocking.
+    mr.approvals_needed = approvals.approvals_left ?? 0;
+    mr.pending_approvers = approvals.pending_approvers;
+    mr.pipeline = this.check_pipelines ? pipeline : undefined;
+    mr.informal_approvals = informal_approvals;
+    mr.last_activity_at = last_activity_at;
+    mr.review_rounds = review_rounds;
+    const metadata = parseFrontmatter(mr.description, this.description_keys);
+    mr.metadata = metadata;
+    mr.notes = this.description_keys
+      .filter((key) => metadata[key] !== undefined)
+      .map((key) => `${key}: ${metadata[key]}`);
+    if (approvals.approvals_left === null) mr.notes.push("approval status unknown");
+    if (informal_approvals !== undefined)
+      mr.notes.push(
+        `informal approvals: ${informal_approvals} :${this.approval_emoji}:`
+      );
+    if (last_activity_at !== undefined)
+      mr.notes.push(
+        `last activity: ${formatDuration(Date.now() - Date.parse(last_activity_at))} ago`
+      );
+    if (review_rounds)
+      mr.notes.push(`${review_rounds} review round${review_rounds > 1 ? "s" : ""}`);
+    if (this.active_author_ids && !this.active_author_ids.includes(mr.author.id))
+      mr.notes.push("author may have left—needs reassignment");
+    if (has_issue === false && this.issues_advisory)
+      mr.notes.push("no linked issue");
+
+    // The author can't approve their own MR, so these rules can never pass.
+    const author_only = this.check_approval_rules
+      ? (approval_rules ?? []).filter(
+          ({ approved, approvals_required, eligible_approvers = [] }) =>
+            !approved &&
+            approvals_required > 0 &&
+            eligible_approvers.length > 0 &&
+            eligible_approvers.every(({ id }) => id === mr.author.id)
         )
+      : [];
+    for (const rule of author_only)
+      mr.notes.unshift(
+        `approval rule \`${rule.name}\` can only be satisfied by author—needs rule fix or another approver`
+      );
+
+    // Advisory approvals are still shown, just not as blockers.
+    const missing_approval = this.#missingApproval(mr, approval_rules);
+    if (this.approvals_advisory && missing_approval)
+      mr.notes.unshift(missing_approval);
+
+    mr.blockers = GitLab.#findBlockers(
+      mr,
+      this.approvals_advisory ? undefined : missing_approval
+    );
+    if (required_job_passed === false)
+      mr.blockers.push({
+        kind: "job",
+        message: `job \`${this.required_job}\` not passed`,
+      });
+    if (has_issue === false && !this.issues_advisory)
+      mr.blockers.push({ kind: "issue", message: "no linked issue" });
+    mr.blockers.push(...(await this.#hookBlockers(mr)));
+  }
+
+  /**
+   * Describes the approval the MR still needs, if any. With `ready_reactions`,
+   * enough `approval_emoji` reactions stand in for GitLab's own approvals.
+   *
+   * With `approval_rule_summary`, lists each unsatisfied rule's approvals so
+   * far, falling back to the count of approvals needed without rule data.
+   *
+   * @param {MergeRequest} mr - MR to check
+   * @param {MRApprovalRule[]} rules - the MR's approval rules, if fetched
+   * @returns {string | undefined} e.g. "requires approval (1)" or "requires
+   *   approval (Code Owners: 1/2, Security: 0/1)"; `undefined` if the MR is
+   *   approved
+   */
+  #missingApproval(
+    mr: MergeRequest,
+    rules?: ReadonlyArray<MRApprovalRule>
+  ): string | undefined {
+    if (this.ready_reactions === undefined) {
+      if (mr.approvals_needed <= 0) return undefined;
+
+      const unsatisfied = this.approval_rule_summary
+        ? (rules ?? [])
+            .filter((rule) => !rule.approved && rule.approvals_required > 0)
+            .map(
+              ({ name, approved_by = [], approvals_required }) =>
+                `${name}: ${approved_by.length}/${approvals_required}`
+            )
+        : [];
+      return `requires approval (${
+        unsatisfied.length > 0 ? unsatisfied.join(", ") : mr.approvals_needed
+      })`;
+    }
+
+    const missing = this.ready_reactions - (mr.informal_approvals ?? 0);
+    return missing > 0
+      ? `requires ${missing} more :${this.approval_emoji}:`
+      : undefined;
+  }
+
+  /**
+   * Finds the MR's latest pipeline, if `check_pipelines` or `required_job` is
+   * enabled.
+   *
+   * Uses the `head_pipeline` embedded in the MR when GitLab includes it,
+   * falling back to a request for the MR's pipelines only when it doesn't.
+   *
+   * @param {MergeRequest} mr - MR to look up
+   * @returns {Promise<Pipeline | undefined>} the latest pipeline, if any
+   */
+  async #pipeline(mr: MergeRequest): Promise<Pipeline | undefined> {
+    if (!this.check_pipelines && !this.required_job) return undefined;
+    if (mr.head_pipeline) return mr.head_pipeline;
+
+    const [latest] = await this.#get<Pipeline[]>(
+      `/merge_requests/${mr.iid}/pipelines`,
+      { per_page: "1" }
+    );
+    return latest;
+  }
+
+  /**
+   * Checks whether the `required_job` succeeded in the MR's latest pipeline.
+   * Retried jobs are only counted by their latest attempt.
+   *
+   * @param {Pipeline} pipeline - the MR's latest pipeline, if any
+   * @returns {Promise<boolean | undefined>} whether the job passed; false if
+   *   there's no pipeline or the job isn't in it, and `undefined` if
+   *   `required_job` isn't configured
+   */
+  async #requiredJobPassed(pipeline?: Pipeline): Promise<boolean | undefined> {
+    if (!this.required_job) return undefined;
+    if (!pipeline) return false;
+
+    const jobs = await this.#getAllPages<Job>(`/pipelines/${pipeline.id}/jobs`, {});
+    return jobs.some(
+      (job) => job.name === this.required_job && job.status === "success"
+    );
+  }
+
+  /**
+   * Fetches the approval rules that apply to the MR, and whether each is
+   * satisfied, if `check_approval_rules` or `approval_rule_summary` is
+   * enabled.
+   *
+   * Editions and tiers without approval rules answer with 403 or 404; the
+   * rules are then treated as unavailable rather than failing the run.
+   *
+   * @param {MergeRequest} mr - MR to look up
+   * @returns {Promise<MRApprovalRule[] | undefined>} the MR's approval rules,
+   *   if enabled and available
+   */
+  async #approvalRules(
+    mr: MergeRequest
+  ): Promise<ReadonlyArray<MRApprovalRule> | undefined> {
+    if (!this.check_approval_rules && !this.approval_rule_summary)
+      return undefined;
+
+    try {
+      const state = await this.#get<MRApprovalState>(
+        `/merge_requests/${mr.iid}/approval_state`
+      );
+      return state.rules ?? [];
+    } catch (e) {
+      if (e instanceof HttpError && [403, 404].includes(e.statusCode))
+        return undefined;
+      throw e;
+    }
+  }
+
+  /**
+   * Checks whether the MR will close any issue, if `requires_issue` is
+   * enabled. GitLab links issues mentioned as e.g. "Closes #123" in the
+   * description.
+   *
+   * @param {MergeRequest} mr - MR to look up
+   * @returns {Promise<boolean | undefined>} whether an issue is linked, if
+   *   enabled
+   */
+  async #hasLinkedIssue(mr: MergeRequest): Promise<boolean | undefined> {
+    if (!this.requires_issue) return undefined;
+
+    const issues = await this.#get<Issue[]>(
+      `/merge_requests/${mr.iid}/closes_issues`,
+      { per_page: "1" }
+    );
+    return issues.length > 0;
+  }
+
+  /**
+   * Counts how many times the MR went through a review round: approved, then
+   * invalidated by new commits. Requires `count_review_rounds`.
+   *
+   * @param {MergeRequest} mr - MR to look up
+   * @returns {Promise<number | undefined>} review rounds, if enabled
+   */
+  async #reviewRounds(mr: MergeRequest): Promise<number | undefined> {
+    if (!this.count_review_rounds) return undefined;
+
+    const notes = await this.#getAllPages<Note>(`/merge_requests/${mr.iid}/notes`, {
+      order_by: "created_at",
+      sort: "asc",
+    });
+
+    let approved = false;
+    let rounds = 0;
+    for (const { body, system } of notes) {
+      if (!system) continue;
+
+      if (body.startsWith("approved this merge request")) {
+        approved = true;
+      } else if (approved && /^added \d+ commits?/.test(body)) {
+        approved = false;
+        rounds++;
+      }
+    }
+
+    return rounds;
+  }
+
+  /**
+   * Queries GitLab for open MRs on the given branch using the trimmed
+   * `view=simple` representation. No approval data is fetched, so this is the
+   * fastest way to list MRs on large projects, but no blockers are computed.
+   *
+   * The simple view doesn't include authors, so if authors were provided, MRs
+   * are requested for each author in turn.
+   *
+   * @returns {SimpleMergeRequest[]} Array of all matching MRs, newest first
+   */
+  async openMergeRequestsSimple(): Promise<Array<SimpleMergeRequest>> {
+    const params = {
+      scope: "all",
+      state: "opened",
+      target_branch: this.target_branch,
+      view: "simple",
+      ...this.#labelParams(),
+    };
+
+    const author_ids = Object.values(this.authors);
+    if (author_ids.length < 1)
+      return this.#listMergeRequests<SimpleMergeRequest>(params);
+
+    const per_author = await Promise.all(
+      author_ids.map((author_id) =>
+        this.#listMergeRequests<SimpleMergeRequest>({
+          ...params,
+          author_id: author_id.toString(),
+        })
       )
     );
 
-    return mrs;
+    return per_author
+      .flat()
+      .sort((a, b) => b.created_at.localeCompare(a.created_at));
+  }
+
+  /**
+   * Queries GitLab for MRs merged into the given branch since `since`.
+   * If authors were provided, restricts MRs authored by those users.
+   *
+   * @param {Date} since - earliest merge to include
+   * @returns {Promise<MergedMergeRequest[]>} Array of all matching MRs
+   */
+  async mergedMergeRequests(since: Date): Promise<Array<MergedMergeRequest>> {
+    // Merging updates an MR, so `updated_after` can only over-fetch.
+    const mrs = await this.#listMergeRequests<MergedMergeRequest>({
+      scope: "all",
+      state: "merged",
+      target_branch: this.target_branch,
+      updated_after: since.toISOString(),
+      ...this.#labelParams(),
+    });
+
+    const author_ids = Object.values(this.authors);
+    return mrs.filter(
+      (mr) =>
+        mr.merged_at !== null &&
+        Date.parse(mr.merged_at) >= since.getTime() &&
+        (author_ids.length < 1 || author_ids.includes(mr.author.id))
+    );
+  }
+
+  /**
+   * Requests the project's MR list, explaining a 404 as the likely cause: a
+   * project id that's wrong or not accessible with the configured token.
+   *
+   * @param {Record<string, string>} params - URL query parameters
+   * @returns {Promise<T[]>} MRs in the requested representation
+   */
+  async #listMergeRequests<T>(params: Record<string, string>): Promise<Array<T>> {
+    try {
+      return await profiler.time("list", () =>
+        this.#getAllPages<T>("/merge_requests", params)
+      );
+    } catch (e) {
+      if (e instanceof HttpError && e.statusCode === 404)
+        throw await this.#projectNotFound();
+      throw e;
+    }
+  }
+
+  /**
+   * Builds an error for an inaccessible `project_id`. If the token can list
+   * projects, suggests accessible project ids closest to the configured one,
+   * in case of a typo.
+   *
+   * @returns {Promise<Error>} error describing the problem
+   */
+  async #projectNotFound(): Promise<Error> {
+    const message = this.#api_token
+      ? [
+          `Project id ${this.project_id} not found or not accessible with this token—`,
+          "check the id and that the token has `read_api` scope on it.",
+        ]
+      : [
+          `Project id ${this.project_id} not found or not public—`,
+          "check the id, or set `api_token` to access private projects.",
+        ];
+
+    if (!this.#api_token) return new Error(message.join(""));
+
+    const distance = (p: Project) => Math.abs(p.id - this.project_id);
+    const nearby = await this.#get<Project[]>(
+      "/projects",
+      { membership: "true", simple: "true", per_page: "100" },
+      GitLab.GITLAB_API_BASE
+    )
+      .then((projects) =>
+        projects
+          .sort((a, b) => distance(a) - distance(b))
+          .slice(0, 5)
+          .map((p) => `    ${p.id} (${p.path_with_namespace})`)
+      )
+      .catch(() => []);
+
+    if (nearby.length > 0)
+      message.push("\nAccessible projects with nearby ids:\n", nearby.join("\n"));
+
+    return new Error(message.join(""));
+  }
+
+  /**
+   * Builds the `labels` list filter. Besides label names, GitLab accepts the
+   * special values `None` (unlabeled MRs) and `Any` (MRs with any label).
+   *
+   * @returns {Record<string, string>} query parameters
+   */
+  #labelParams(): Record<string, string> {
+    return this.labels ? { labels: this.labels } : {};
+  }
+
+  /**
+   * Fetches the MR's approval status, using the cached value instead if the
+   * MR hasn't been updated since it was stored.
+   *
+   * @param {MergeRequest} mr - MR to look up
+   * @param {ApprovalCache} cache - optional approval cache
+   * @returns {Promise<Approvals>} approvals still required, and by whom
+   */
+  async #approvals(mr: MergeRequest, cache?: ApprovalCache): Promise<Approvals> {
+    const key = `${this.project_id}!${mr.iid}`;
+
+    const cached = cache?.get(key, mr.updated_at);
+    if (cached !== undefined) {
+      log(`/merge_requests/${mr.iid}/approvals - cached.`);
+      return cached;
+    }
+
+    const status = await profiler.time(
+      "approvals",
+      () => this.#get<MRApprovalStatus>(`/merge_requests/${mr.iid}/approvals`),
+      { iid: mr.iid }
+    );
+    const approvals = parseApprovals(status);
+
+    cache?.set(key, mr.updated_at, approvals);
+    return approvals;
+  }
+
+  /**
+   * Counts the MR's `approval_emoji` reactions, ignoring the author's own.
+   *
+   * @param {MergeRequest} mr - MR to look up
+   * @returns {Promise<number | undefined>} reaction count, or `undefined` if
+   *   `approval_emoji` isn't configured
+   */
+  async #informalApprovals(mr: MergeRequest): Promise<number | undefined> {
+    if (!this.approval_emoji) return undefined;
+
+    const awards = await this.#get<AwardEmoji[]>(
+      `/merge_requests/${mr.iid}/award_emoji`,
+      { per_page: "100" }
+    );
+
+    return awards.filter(
+      (award) =>
+        award.name === this.approval_emoji && award.user.id !== mr.author.id
+    ).length;
+  }
+
+  /**
+   * Finds when the MR was last commented on or approved, if
+   * `show_last_activity` is enabled. Unlike `updated_at`, this ignores
+   * changes such as label edits or pipeline updates.
+   *
+   * Approvals are recorded as system notes, alongside those for pushes, label
+   * changes and the like, so notes are paged through newest first until a
+   * comment or approval turns up.
+   *
+   * @param {MergeRequest} mr - MR to look up
+   * @returns {Promise<string | undefined>} timestamp of the latest comment or
+   *   approval, if any
+   */
+  async #lastActivity(mr: MergeRequest): Promise<string | undefined> {
+    if (!this.show_last_activity) return undefined;
+
+    const uri = `/merge_requests/${mr.iid}/notes`;
+    let next: string | undefined = "1";
+    while (next) {
+      const res: ApiResponse<Note[]> = await this.#getResponse<Note[]>(uri, {
+        // Editing or resolving an old comment isn't new activity.
+        order_by: "created_at",
+        sort: "desc",
+        per_page: GitLab.PER_PAGE.toString(),
+        page: next,
+      });
+
+      const latest = res.data.find(
+        ({ body, system }) =>
+          !system || body.startsWith("approved this merge request")
+      );
+      if (latest) return latest.created_at;
+
+      next = header(res.headers, "x-next-page");
+    }
+
+    return undefined;
+  }
+
+  /**
+   * Runs the configured `blocker_hook_command`, if any, for the MR.
+   * Hook failures are reported as warnings and contribute no blockers.
+   *
+   * @param {MergeRequest} mr - MR to pass to the hook
+   * @returns {Promise<Blocker[]>} blockers reported by the hook
+   */
+  async #hookBlockers(mr: MergeRequest): Promise<Array<Blocker>> {
+    if (!this.blocker_hook_command) return [];
+
+    try {
+      const messages = await runBlockerHook(this.blocker_hook_command, mr);
+      return messages.map((message): Blocker => ({ kind: "hook", message }));
+    } catch (e) {
+      console.warn(
+        `blocker hook failed for !${mr.iid}: ${e instanceof Error ? e.message : e}`
+      );
+      return [];
+    }
+  }
+
+  /**
+   * Adds advisory notes to each MR. Notes are informational only and, unlike
+   * blockers, don't affect whether an MR is ready to merge.
+   *
+   * @param {MergeRequest[]} mrs - all fetched MRs; notes may depend on the set
+   */
+  static #addNotes(mrs: ReadonlyArray<MergeRequest>): void {
+    const open_by_author = new Map<number, number>();
+    for (const { author } of mrs) {
+      open_by_author.set(author.id, (open_by_author.get(author.id) ?? 0) + 1);
+    }
+
+    for (const mr of mrs) {
+      const others = (open_by_author.get(mr.author.id) ?? 1) - 1;
+      if (others > 0)
+        mr.notes.push(`author has ${others} other open MR${others > 1 ? "s" : ""}`);
+    }
   }
 
   /**
@@ -145,58 +1073,187 @@ export class GitLab implements Omit<GitLabConfig, "api_token"> {
    * the merging of the MR, and updates the `MergeRequest` object.
    *
    * @param {MergeRequest} mr - MR to derive blockers from
-   * @returns {string[]} Array of blockers; if empty, no blockers were found
+   * @param {string} missing_approval - the approval still needed, if it
+   *   should block the MR
+   * @returns {Blocker[]} Array of blockers; if empty, no blockers were found
    */
-  static #findBlockers(mr: MergeRequest): Array<string> {
+  static #findBlockers(
+    mr: MergeRequest,
+    missing_approval?: string
+  ): Array<Blocker> {
     const {
       blocking_discussions_resolved: threads_resolved,
       has_conflicts,
       merge_status,
-      approvals_needed,
     } = mr;
-    const blockers = [];
+    const blockers: Array<Blocker> = [];
+    const block = (kind: BlockerKind, message: string) =>
+      blockers.push({ kind, message });
 
-    if (!threads_resolved) blockers.push("unresolved threads");
-    if (has_conflicts) blockers.push("has conflicts");
+    if (!threads_resolved) block("threads", "unresolved threads");
+    if (has_conflicts) block("conflicts", "has conflicts");
     if (merge_status.includes("cannot_be_merged"))
-      blockers.push("cannot be merged");
-    if (approvals_needed > 0)
-      blockers.push(`requires approval (${approvals_needed})`);
+      block("unmergeable", "cannot be merged");
+    if (missing_approval) block("approval", missing_approval);
+    if (mr.pipeline && !PASSED_PIPELINE.includes(mr.pipeline.status))
+      block("pipeline", `pipeline ${mr.pipeline.status}`);
 
     return blockers;
   }
 
   /**
-   * Makes GitLab API request and wraps it in a Promise
+   * Fetches every page of a paginated list.
+   *
+   * The first response's `X-Total-Pages` header tells how many pages there
+   * are, so the rest can be fetched concurrently. GitLab omits that header for
+   * very large lists, in which case pages are followed one at a time using
+   * `X-Next-Page`.
+   *
+   * @param {string} uri - GitLab API URI; appended to base URL
+   * @param {Record<string, string>} params - URL query parameters
+   * @returns {Promise<T[]>} items from all pages, in page order
+   */
+  async #getAllPages<T>(
+    uri: string,
+    params: Record<string, string>
+  ): Promise<Array<T>> {
+    const page = (n: number | string) => ({
+      ...params,
+      per_page: GitLab.PER_PAGE.toString(),
+      page: n.toString(),
+    });
+
+    const fetchPage = (n: number | string) =>
+      profiler.time("page", () => this.#getResponse<T[]>(uri, page(n)), {
+        uri,
+        page: n,
+      });
+
+    const first = await fetchPage(1);
+    const total_pages = Number(header(first.headers, "x-total-pages"));
+
+    if (total_pages > 1) {
+      const numbers = Array.from({ length: total_pages - 1 }, (_, i) => i + 2);
+      const rest = await mapLimit(numbers, GitLab.MAX_CONCURRENT_PAGES, (n) =>
+        fetchPage(n).then((res) => res.data)
+      );
+      return first.data.concat(...rest);
+    }
+
+    const items = [...first.data];
+    let next = header(first.headers, "x-next-page");
+    while (next) {
+      const res = await fetchPage(next);
+      items.push(...res.data);
+      next = header(res.headers, "x-next-page");
+    }
+
+    return items;
+  }
+
+  /**
+   * Makes GitLab API request, retrying transient failures with backoff
    *
    * @param {string} uri - GitLab API URI; appended to base URL
    * @param {Record<string, string>} params: optional URL query parameters
+   * @param {string} base: optional base URL; defaults to the project's API URL
    * @returns {Promise<T>} API result as the requested type
    */
   async #get<T>(
     uri: string,
-    params?: Record<string, string>
+    params?: Record<string, string>,
+    base?: string
   ): Promise<T> {
-    const baseURL = `${GitLab.GITLAB_API_BASE}/projects/${this.project_id}`;
+    return (await this.#getResponse<T>(uri, params, base)).data;
+  }
+
+  /**
+   * Makes GitLab API request, retrying transient failures with backoff
+   *
+   * @param {string} uri - GitLab API URI; appended to base URL
+   * @param {Record<string, string>} params: optional URL query parameters
+   * @param {string} base: optional base URL; defaults to the project's API URL
+   * @returns {Promise<ApiResponse<T>>} API result and response headers
+   */
+  async #getResponse<T>(
+    uri: string,
+    params?: Record<string, string>,
+    base?: string
+  ): Promise<ApiResponse<T>> {
+    for (let attempt = 1; ; attempt++) {
+      try {
+        return await this.#request<T>(uri, params, base);
+      } catch (e) {
+        if (attempt >= GitLab.MAX_ATTEMPTS || !this.#isRetryable(e)) throw e;
+
+        const delay = 1000 * 2 ** (attempt - 1);
+        log(`${uri} - ${e instanceof Error ? e.message : e}; retrying in ${delay}ms`);
+        await new Promise((resolve) => setTimeout(resolve, delay));
+      }
+    }
+  }
+
+  #isRetryable(e: unknown): boolean {
+    if (e instanceof HttpError) return this.retry_on_status.includes(e.statusCode);
+
+    const code = (e as NodeJS.ErrnoException)?.code;
+    return code !== undefined && GitLab.RETRY_ON_ERROR.includes(code);
+  }
+
+  /**
+   * Makes a single GitLab API request and wraps it in a Promise
+   *
+   * @param {string} uri - GitLab API URI; appended to base URL
+   * @param {Record<string, string>} params: optional URL query parameters
+   * @param {string} base: optional base URL; defaults to the project's API URL
+   * @returns {Promise<ApiResponse<T>>} API result and response headers
+   */
+  async #request<T>(
+    uri: string,
+    params?: Record<string, string>,
+    base?: string
+  ): Promise<ApiResponse<T>> {
+    const baseURL =
+      base ?? `${GitLab.GITLAB_API_BASE}/projects/${this.project_id}`;
     const url = new URL(`${baseURL}${uri}`);
     url.search = new URLSearchParams(params).toString();
 
     const logUrl = url.toString().slice(baseURL.length);
     log(`${logUrl} - requesting...`);
 
-    const options: RequestOptions = {
-      headers: { authorization: `Bearer ${this.#api_token}` },
-    };
+    const options: RequestOptions = { headers: requestHeaders(this.#api_token) };
 
     return new Promise((resolve, reject) => {
       const req = httpsGet(url, options, (res: IncomingMessage) => {
         const { statusCode } = res;
         if (statusCode && (statusCode < 200 || statusCode >= 300)) {
-          return reject(new Error(`statusCode=${statusCode}`));
+          res.resume();
+          return reject(new HttpError(statusCode, logUrl));
+        }
+
+        // Don't buffer unbounded responses, e.g. from a misconfigured URL.
+        const tooLarge = () => {
+          req.destroy();
+          reject(
+            new Error(
+              `${logUrl} - response exceeds \`max_response_bytes\` (${this.max_response_bytes})`
+            )
+          );
+        };
+        if (Number(res.headers["content-length"]) > this.max_response_bytes) {
+          return tooLarge();
         }
 
         const chunks: Uint8Array[] = [];
-        res.on("data", (chunk: Uint8Array) => chunks.push(chunk));
+        let received = 0;
+        res.on("data", (chunk: Uint8Array) => {
+          received += chunk.length;
+          if (received > this.max_response_bytes) {
+            tooLarge();
+          } else {
+            chunks.push(chunk);
+          }
+        });
         res.on("end", () => {
           const data = Buffer.concat(chunks).toString();
           let result;
@@ -206,7 +1263,7 @@ export class GitLab implements Omit<GitLabConfig, "api_token"> {
             reject(e);
           }
           log(`${logUrl} - received ${data.length} bytes.`);
-          resolve(result);
+          resolve({ data: result, headers: res.headers });
         });
       });
       req.on("error", reject);
@@ -214,3 +1271,20 @@ export class GitLab implements Omit<GitLabConfig, "api_token"> {
     });
   }
 }
+
+/**
+ * Builds the headers sent with each API request. Public projects can be read
+ * anonymously, so without a token no `authorization` header is sent at all,
+ * rather than an empty `Bearer`.
+ *
+ * @param {string} api_token - personal access token, if any
+ * @returns {Record<string, string>} request headers
+ */
+export function requestHeaders(api_token?: string): Record<string, string> {
+  return api_token ? { authorization: `Bearer ${api_token}` } : {};
+}
+
+function header(headers: IncomingHttpHeaders, name: string): string | undefined {
+  const value = headers[name];
+  return Array.isArray(value) ? value[0] : value;
+}
@@ -0,0 +1,39 @@
+import { exec } from "child_process";
+
+import { MergeRequest } from "./gitlab";
+
+const HOOK_TIMEOUT_MS = 10 * 1000;
+
+/**
+ * Runs a user-supplied blocker hook for an MR.
+ *
+ * The command is run by the shell with the MR as JSON on stdin. Each
+ * non-empty line it writes to stdout is treated as a blocker.
+ *
+ * @param {string} command - shell command to run
+ * @param {MergeRequest} mr - MR passed to the hook
+ * @returns {Promise<string[]>} blocker messages reported by the hook
+ */
+export function runBlockerHook(
+  command: string,
+  mr: MergeRequest
+): Promise<Array<string>> {
+  return new Promise((resolve, reject) => {
+    const child = exec(command, { timeout: HOOK_TIMEOUT_MS }, (err, stdout) => {
+      if (err?.killed)
+        return reject(new Error(`timed out after ${HOOK_TIMEOUT_MS}ms`));
+      if (err) return reject(err);
+
+      resolve(
+        stdout
+          .split("\n")
+          .map((line) => line.trim())
+          .filter(Boolean)
+      );
+    });
+
+    // The hook may exit without reading its input; that's not an error.
+    child.stdin?.on("error", () => undefined);
+    child.stdin?.end(JSON.stringify(mr));
+  });
+}
@@ -1,38 +1,359 @@
 #!/usr/bin/env node
 
 import { readFile } from "fs/promises";
-import { env, stdout } from "process";
+import { argv, env, stdin, stdout } from "process";
 
-import { GitLab } from "./gitlab";
-import { formatMRs, groupBy, log } from "./util";
+import { auditRules, formatAudit } from "./audit";
+import { ApprovalCache } from "./cache";
+import { ConfigError, exitStatus } from "./exit";
+import {
+  Format,
+  SECTION_HEADERS,
+  Section,
+  buildReport,
+  formatDetail,
+  formatJson,
+  parseSectionOrder,
+  renderAnnotations,
+  renderMRs,
+  renderOrg,
+  renderSimpleMRs,
+  writeChunks,
+} from "./format";
+import {
+  GitLab,
+  GitLabConfig,
+  MergeRequest,
+  SimpleMergeRequest,
+} from "./gitlab";
+import {
+  NUDGE_RULES,
+  NudgeRule,
+  formatNudges,
+  nudges,
+  parseNudgeRules,
+} from "./nudge";
+import { OnCallConfig, currentOnCall, formatOnCall } from "./oncall";
+import { Options, parseArgs } from "./options";
+import { profiler } from "./profile";
+import { serve } from "./serve";
+import { toToml } from "./toml";
+import { formatTrend, weeklyMerges } from "./trend";
+import { tui } from "./tui";
+import {
+  anonymize,
+  formatDuration,
+  formatTimestamp,
+  groupBy,
+  log,
+} from "./util";
 
 const RC_FILE = "~/.mrstat.json";
+const CACHE_FILE = "~/.mrstat-cache.json";
+
+const SERVE_HOST = "127.0.0.1";
+const SERVE_PORT = 8080;
+const SERVE_CACHE_TTL = 60 * 1000;
+
+const TREND_WEEKS = 8;
+
+interface Config extends GitLabConfig {
+  readonly change_marker?: string;
+  readonly exit_codes?: Record<string, number>;
+  readonly label_emoji?: Record<string, string>;
+  readonly nudge_rules?: Array<NudgeRule>;
+  readonly on_call?: OnCallConfig;
+  readonly section_order?: Array<string>;
+  readonly timezone?: string;
+}
 
 async function main() {
+  const options = await configuring(() => parseArgs(argv.slice(2)));
+  const { profileTime } = options;
+  if (profileTime === undefined) return run(options);
+
+  profiler.enable();
+  try {
+    await run(options);
+  } finally {
+    await profiler.save(profileTime);
+    log(`Wrote timing trace to ${profileTime}`);
+  }
+}
+
+async function run(options: Options) {
+  const { config, gitlab } = await configuring(() => loadConfig(options));
+  if (!options.anonymize) log(gitlab);
+
+  if (options.command === "serve") {
+    // Failing to listen, e.g. on a port in use, is a problem with `--bind`.
+    await configuring(() =>
+      serve(
+        async () =>
+          buildReport(gitlab.target_branch, await fetchMRs(gitlab, options)),
+        {
+          host: options.bind?.host ?? SERVE_HOST,
+          port: options.bind?.port ?? SERVE_PORT,
+          cacheTtl: options.cacheTtl ?? SERVE_CACHE_TTL,
+          compactJson: options.compactJson,
+          label_emoji: config.label_emoji,
+          sectionOrder: parseSectionOrder(config.section_order ?? []),
+          timezone: config.timezone,
+        }
+      )
+    );
+    return;
+  }
+
+  // Without a terminal to draw on, fall back to the one-shot report.
+  if (options.command === "tui" && stdin.isTTY && stdout.isTTY) {
+    await tui(
+      async () =>
+        buildReport(gitlab.target_branch, await fetchMRs(gitlab, options)),
+      {
+        interval: options.interval,
+        changeMarker: config.change_marker,
+        timezone: config.timezone,
+      }
+    );
+    return;
+  }
+
+  if (options.command === "trend") {
+    const weeks = options.weeks ?? TREND_WEEKS;
+    const since = new Date(Date.now() - weeks * 7 * 24 * 60 * 60 * 1000);
+    const merged = await gitlab.mergedMergeRequests(since);
+    stdout.write(
+      formatTrend(
+        gitlab.target_branch,
+        weeklyMerges(merged, weeks, gitlab.authors),
+        config.timezone
+      )
+    );
+    return;
+  }
+
+  if (options.command === "audit") {
+    const audits = auditRules(await gitlab.approvalRules());
+    stdout.write(formatAudit(audits));
+    if (audits.some(({ problems }) => problems.length > 0))
+      exitStatus.raise("audit_failed");
+    return;
+  }
+
+  if (options.command === "show" && options.iid !== undefined) {
+    const cache = await loadCache();
+    const mr = await gitlab.mergeRequest(options.iid, cache);
+    await cache.save();
+
+    const [shown] = options.anonymize ? anonymize([mr]) : [mr];
+    if (shown) stdout.write(formatDetail(shown, config.label_emoji, config.timezone));
+    if (mr.blockers.length > 0) exitStatus.raise("blocked");
+    return;
+  }
+
+  if (options.simple) {
+    const simple_mrs = await gitlab.openMergeRequestsSimple();
+    if (options.skipIfEmpty && simple_mrs.length === 0) return skipEmpty();
+
+    await profiler.time("render", () =>
+      printMarkdown(
+        gitlab.target_branch,
+        simple_mrs.length > 0 ? renderSimpleMRs("Open", simple_mrs) : []
+      )
+    );
+    enforceSla(simple_mrs, options.failIfOlderThan);
+    return;
+  }
+
+  const open_mrs = await fetchMRs(gitlab, options);
+  if (options.skipIfEmpty && open_mrs.length === 0) return skipEmpty();
+  if (open_mrs.some((mr) => mr.blockers.length > 0)) exitStatus.raise("blocked");
+
+  const format =
+    options.format ?? (env["GITHUB_ACTIONS"] === "true" ? "gha" : "markdown");
+
+  await profiler.time("render", () =>
+    render(gitlab, config, options, open_mrs, format)
+  );
+  enforceSla(open_mrs, options.failIfOlderThan);
+}
+
+/**
+ * Writes the report for `mrs` to stdout in the given format.
+ *
+ * @param {GitLab} gitlab - configured GitLab client
+ * @param {Config} config - loaded configuration
+ * @param {Options} options - command-line options
+ * @param {MergeRequest[]} open_mrs - MRs to report
+ * @param {Format} format - output format
+ */
+async function render(
+  gitlab: GitLab,
+  config: Config,
+  options: Options,
+  open_mrs: ReadonlyArray<MergeRequest>,
+  format: Format
+): Promise<void> {
+  const order = parseSectionOrder(config.section_order ?? []);
+
+  if (format === "gha") {
+    await writeChunks(stdout, renderAnnotations(open_mrs));
+  } else if (format === "json") {
+    stdout.write(
+      formatJson(buildReport(gitlab.target_branch, open_mrs), options.compactJson)
+    );
+  } else if (format === "toml") {
+    stdout.write(toToml(buildReport(gitlab.target_branch, open_mrs)));
+  } else if (format === "org") {
+    await writeChunks(
+      stdout,
+      renderOrg(
+        buildReport(gitlab.target_branch, open_mrs),
+        options.orgLevel,
+        order
+      )
+    );
+  } else {
+    const mrs = groupBy(
+      (mr): Section => (mr.blockers.length > 0 ? "blocked" : "ready"),
+      open_mrs
+    );
+
+    const { label_emoji } = config;
+    const sections = function* () {
+      // The rotation names real people, so it's left out of shared reports.
+      if (config.on_call && !options.anonymize)
+        yield formatOnCall(config.on_call, open_mrs);
+
+      for (const section of order) {
+        const section_mrs = mrs.get(section);
+        if (section_mrs)
+          yield* renderMRs(SECTION_HEADERS[section], section_mrs, label_emoji);
+      }
+
+      if (options.nudge)
+        yield formatNudges(
+          nudges(
+            open_mrs,
+            config.nudge_rules ?? NUDGE_RULES,
+            options.anonymize ? {} : gitlab.authors
+          )
+        );
+    };
+
+    await printMarkdown(gitlab.target_branch, sections());
+  }
+}
+
+/**
+ * Reads and validates the configuration file, and creates the GitLab client
+ * with any overrides from the command line.
+ *
+ * @param {Options} options - command-line options
+ * @returns {Promise<{config: Config, gitlab: GitLab}>} configuration and client
+ */
+async function loadConfig(
+  options: Options
+): Promise<{ config: Config; gitlab: GitLab }> {
   const path = RC_FILE.replace("~", env["HOME"] ?? "");
   log(`Checking for configuration file ${path}`);
 
-  const gitlab = await readFile(path, { encoding: "utf8" }).then((content) => {
-    const config = JSON.parse(content);
-    return new GitLab(config);
+  const config: Config = await profiler.time("config", () =>
+    readFile(path, { encoding: "utf8" }).then((content) => JSON.parse(content))
+  );
+  if (config.exit_codes) exitStatus.configure(config.exit_codes);
+  if (config.nudge_rules) parseNudgeRules(config.nudge_rules);
+  if (config.section_order) parseSectionOrder(config.section_order);
+  if (config.on_call) currentOnCall(config.on_call);
+  if (options.reviewing && !config.reviewer_usernames?.length)
+    throw new Error(
+      `\`--reviewing\` requires \`reviewer_usernames\` to be configured`
+    );
+  if (config.timezone) {
+    try {
+      formatTimestamp(Date.now(), config.timezone);
+    } catch {
+      throw new Error(
+        `unknown \`timezone\` ${config.timezone}: expected an IANA name like Europe/Berlin`
+      );
+    }
+  }
+
+  const gitlab = new GitLab({
+    ...config,
+    approval_rule_summary: options.approvalRules ?? config.approval_rule_summary,
+    labels: options.labels ?? config.labels,
+    requires_issue: options.requiresIssue ?? config.requires_issue,
+    reviewer_usernames: options.reviewing ? config.reviewer_usernames : undefined,
   });
-  log(gitlab);
 
-  const mrs = groupBy(
-    (mr) => (mr.blockers.length > 0 ? "blocked" : "ready"),
-    await gitlab.openMergeRequests()
-  );
+  return { config, gitlab };
+}
+
+/**
+ * Runs `f`, reporting anything it throws as a `ConfigError`, so that it sets
+ * the `config_error` exit status.
+ *
+ * @param {() => T | Promise<T>} f - reads or validates configuration
+ * @returns {Promise<T>} the result of `f`
+ */
+async function configuring<T>(f: () => T | Promise<T>): Promise<T> {
+  try {
+    return await f();
+  } catch (e) {
+    throw new ConfigError(e);
+  }
+}
 
-  // These assertions are fine. `get()` will succeed if `has()` is true.
-  /* eslint @typescript-eslint/no-non-null-assertion: off */
-  const output = [
-    `\n*Open MRs against \`${gitlab.target_branch}\`:*\n`,
-    mrs.has("ready") && formatMRs("Ready to Merge", mrs.get("ready")!),
-    mrs.has("blocked") && formatMRs("Blocked", mrs.get("blocked")!),
-  ]
-    .filter(Boolean)
-    .join("");
+/**
+ * Produces no output, but sets an exit status so scripts can tell that there
+ * were no MRs to report.
+ */
+function skipEmpty(): void {
+  log("No matching MRs; skipping output.");
+  exitStatus.raise("empty");
+}
 
+function loadCache(): Promise<ApprovalCache> {
+  return ApprovalCache.load(CACHE_FILE.replace("~", env["HOME"] ?? ""));
+}
+
+/**
+ * Fetches open MRs and applies the filtering and anonymization requested by
+ * the command-line options.
+ *
+ * @param {GitLab} gitlab - configured GitLab client
+ * @param {Options} options - command-line options
+ * @returns {Promise<MergeRequest[]>} MRs to report
+ */
+async function fetchMRs(
+  gitlab: GitLab,
+  options: Options
+): Promise<Array<MergeRequest>> {
+  const cache = await loadCache();
+  const fetched = await gitlab.openMergeRequests(cache);
+  await cache.save();
+
+  const { onlyBlockers } = options;
+  const matching = onlyBlockers
+    ? fetched.filter((mr) => mr.blockers.some((b) => onlyBlockers.includes(b.kind)))
+    : fetched;
+
+  return options.anonymize ? anonymize(matching) : matching;
+}
+
+/**
+ * Prints the Slack-style markdown report to stdout, streaming the sections as
+ * they're formatted.
+ *
+ * @param {string} target_branch - branch the MRs target
+ * @param {Iterable<string>} sections - formatted report sections
+ */
+async function printMarkdown(
+  target_branch: string,
+  sections: Iterable<string>
+): Promise<void> {
   console.warn("\nOutput can safely be piped to clipboard.\n");
   console.warn(`E.g., for macOS: mrstat | pbcopy\n`);
 
@@ -42,8 +363,49 @@ async function main() {
   // If the output is not piped (`stdout.isTTY === true`), write BEGIN/END
   // markers to make it easier to see/copy with the mouse.
   if (stdout.isTTY) console.warn("===== BEGIN MARKDOWN =====");
-  console.log(output);
+  await writeChunks(stdout, [`\n*Open MRs against \`${target_branch}\`:*\n`]);
+  await writeChunks(stdout, sections);
+  await writeChunks(stdout, ["\n"]);
   if (stdout.isTTY) console.warn("===== END MARKDOWN =====\n");
 }
 
-main().catch((err) => console.error(err.message));
+/**
+ * Sets the SLA exit status if any MR is older than `limit`.
+ *
+ * @param {SimpleMergeRequest[]} mrs - MRs to check
+ * @param {number} limit - maximum allowed age in milliseconds, if any
+ */
+function enforceSla(
+  mrs: ReadonlyArray<SimpleMergeRequest>,
+  limit: number | undefined
+): void {
+  if (limit !== undefined && checkSla(mrs, limit) > 0)
+    exitStatus.raise("sla_violation");
+}
+
+/**
+ * Reports MRs that have been open longer than `limit` to stderr.
+ *
+ * @param {SimpleMergeRequest[]} mrs - MRs to check
+ * @param {number} limit - maximum allowed age in milliseconds
+ * @returns {number} count of MRs violating the SLA
+ */
+function checkSla(mrs: ReadonlyArray<SimpleMergeRequest>, limit: number): number {
+  const now = Date.now();
+  const violations = mrs.filter((mr) => now - Date.parse(mr.created_at) > limit);
+
+  if (violations.length > 0) {
+    console.warn(`${violations.length} MR(s) open longer than ${formatDuration(limit)}:`);
+    for (const mr of violations) {
+      const over = now - Date.parse(mr.created_at) - limit;
+      console.warn(`    !${mr.iid} ${mr.title} - over by ${formatDuration(over)}`);
+    }
+  }
+
+  return violations.length;
+}
+
+main().catch((err) => {
+  console.error(err.message);
+  exitStatus.raise(err instanceof ConfigError ? "config_error" : "api_error");
+});
@@ -0,0 +1,80 @@
+import { Author, MergeRequest } from "./gitlab";
+import { parseChoice } from "./util";
+
+export const NUDGE_RULES = ["approval", "threads", "conflicts"] as const;
+
+export type NudgeRule = typeof NUDGE_RULES[number];
+
+/**
+ * Parses `nudge_rules`, so that a misspelled rule is reported rather than
+ * silently never nudging anyone.
+ *
+ * @param {Array<string>} rules - user-supplied rule names
+ * @returns {Array<NudgeRule>} the rules
+ */
+export function parseNudgeRules(rules: ReadonlyArray<string>): Array<NudgeRule> {
+  return rules.map((rule) => parseChoice(NUDGE_RULES, rule, "nudge rule"));
+}
+
+/**
+ * Builds a list of whom to ping about each blocked MR, grouped by person.
+ *
+ * - `approval`: pending approvers, for MRs that need approval
+ * - `threads`: the author, for MRs with unresolved threads
+ * - `conflicts`: the author, for MRs with merge conflicts
+ *
+ * People are mentioned by their friendly name from `authors` when they're
+ * listed there, and by GitLab username otherwise.
+ *
+ * @param {Array<MergeRequest>} mrs - MRs to generate nudges for
+ * @param {Array<NudgeRule>} rules - which rules to apply
+ * @param {Record<string, number>} authors - friendly names to GitLab user ids
+ * @returns {Map<string, Array<string>>} nudges, keyed by person to mention
+ */
+export function nudges(
+  mrs: ReadonlyArray<MergeRequest>,
+  rules: ReadonlyArray<NudgeRule>,
+  authors: Readonly<Record<string, number>>
+): Map<string, Array<string>> {
+  const names = new Map(Object.entries(authors).map(([name, id]) => [id, name]));
+  const mention = (user: Author) => names.get(user.id) ?? user.username;
+
+  const result = new Map<string, Array<string>>();
+  const nudge = (user: Author, message: string) => {
+    const who = mention(user);
+    result.set(who, [...(result.get(who) ?? []), message]);
+  };
+
+  for (const mr of mrs) {
+    const has = (kind: string) => mr.blockers.some((b) => b.kind === kind);
+
+    if (rules.includes("approval") && has("approval")) {
+      for (const approver of mr.pending_approvers ?? []) {
+        nudge(approver, `!${mr.iid} needs your approval`);
+      }
+    }
+    if (rules.includes("threads") && has("threads"))
+      nudge(mr.author, `!${mr.iid} has unresolved threads`);
+    if (rules.includes("conflicts") && has("conflicts"))
+      nudge(mr.author, `!${mr.iid} has conflicts`);
+  }
+
+  return result;
+}
+
+/**
+ * Formats nudges as a Slack-style markdown section.
+ *
+ * @param {Map<string, Array<string>>} nudges - nudges, keyed by person
+ * @returns {string} Slack-formatted text; empty if there are no nudges
+ */
+export function formatNudges(nudges: ReadonlyMap<string, Array<string>>): string {
+  if (nudges.size === 0) return "";
+
+  const output = ["\n*Nudges:*\n"];
+  for (const [who, messages] of nudges) {
+    output.push(`* @${who}: ${messages.join(", ")}\n`);
+  }
+
+  return output.join("");
+}
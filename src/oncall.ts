@@ -0,0 +1,67 @@
+import { MergeRequest } from "./gitlab";
+import { parseDuration } from "./util";
+
+const DEFAULT_SHIFT = "1w";
+
+/**
+ * A rotation of first responders for MRs nobody is reviewing yet
+ */
+export interface OnCallConfig {
+  /** People in the rotation, in order; e.g. names from `authors` */
+  readonly people: Array<string>;
+  /** When the first person's first shift started, e.g. `2022-04-04` */
+  readonly start: string;
+  /** Length of each shift, e.g. `1w`; defaults to a week */
+  readonly shift?: string;
+  /** Mention the first responder with `@`, to notify them in Slack */
+  readonly mention?: boolean;
+}
+
+/**
+ * Finds who's on call at `now`. Shifts repeat through `people` in order,
+ * starting from `start`.
+ *
+ * @param {OnCallConfig} on_call - the rotation
+ * @param {number} now - time to check, in ms since the epoch
+ * @returns {string} the person on call
+ */
+export function currentOnCall(on_call: OnCallConfig, now = Date.now()): string {
+  const { people } = on_call;
+  const start = Date.parse(on_call.start);
+  const shift = parseDuration(on_call.shift ?? DEFAULT_SHIFT);
+
+  if (!Array.isArray(people) || people.length < 1)
+    throw new Error("`on_call` requires at least one person in `people`");
+  if (shift <= 0)
+    throw new Error(
+      `invalid \`on_call\` shift \`${on_call.shift}\`: expected a duration longer than 0`
+    );
+  if (Number.isNaN(start))
+    throw new Error(
+      `invalid \`on_call\` start \`${on_call.start}\`: expected a date like 2022-04-04`
+    );
+
+  const shifts = Math.floor((now - start) / shift);
+  const n = people.length;
+  return people[((shifts % n) + n) % n] ?? "";
+}
+
+/**
+ * Formats a header line calling out the current first responder, and how
+ * many MRs have no reviewer yet.
+ *
+ * @param {OnCallConfig} on_call - the rotation
+ * @param {Array<MergeRequest>} mrs - reported MRs
+ * @returns {string} Slack-formatted line
+ */
+export function formatOnCall(
+  on_call: OnCallConfig,
+  mrs: ReadonlyArray<MergeRequest>
+): string {
+  const who = currentOnCall(on_call);
+  const unreviewed = mrs.filter((mr) => (mr.reviewers ?? []).length === 0).length;
+  const count =
+    unreviewed > 0 ? ` (${unreviewed} unreviewed MR${unreviewed > 1 ? "s" : ""})` : "";
+
+  return `_First responder: ${on_call.mention ? "@" : ""}${who}${count}_\n`;
+}
@@ -0,0 +1,324 @@
+import { FORMATS, Format } from "./format";
+import { BLOCKER_KINDS, BlockerKind } from "./gitlab";
+import { parseChoice, parseDuration } from "./util";
+
+export const COMMANDS = ["audit", "serve", "show", "trend", "tui"] as const;
+
+export type Command = typeof COMMANDS[number];
+
+const USAGE = `usage: mrstat [audit | serve | show <iid> | trend | tui] [options]
+
+  audit                            check the project's approval rules for
+                                   rules no one can satisfy
+  serve                            run an HTTP server rendering the report
+                                   at /report.json and /report.html
+  show <iid>                       show the details of a single MR
+  trend                            chart merges per week, for the team and
+                                   for each author
+  tui                              browse the report interactively
+
+  --anonymize                      replace authors and URLs with placeholders
+  --approval-rules                 list unsatisfied approval rules of MRs
+                                   that need approval
+  --compact-json                   minify \`--format json\` (and \`serve\`'s JSON)
+  --fail-if-older-than <duration>  fail if any MR has been open this long
+  --format <format>                output format (${FORMATS.join(", ")})
+  --labels <labels>                only report MRs with all of these labels,
+                                   or \`None\` (unlabeled) or \`Any\` (labeled)
+  --nudge                          list whom to ping about blocked MRs
+  --org-level <n>                  top heading level for \`--format org\`
+  --profile-time <path>            write a JSON trace of how long each phase
+                                   of the run took
+  --only-blocker <kind>[,<kind>]   only report MRs with these blockers
+                                   (${BLOCKER_KINDS.join(", ")})
+  --requires-issue                 flag MRs that aren't linked to an issue
+  --reviewing                      only report MRs \`reviewer_usernames\` review
+  --simple                         fast listing without approvals or blockers
+  --skip-if-empty                  print nothing if there are no MRs to report
+
+serve options:
+  --bind [<host>:]<port>           address to listen on (default 127.0.0.1:8080)
+  --cache-ttl <duration>           reuse a fetched report this long (default 1m)
+
+trend options:
+  --weeks <n>                      how many weeks to chart (default 8)
+
+tui options:
+  --interval <duration>            also refresh the report this often`;
+
+/**
+ * Command-line options
+ */
+export interface Options {
+  /** Subcommand; unset prints the report once */
+  command?: Command;
+  /** MR to `show` */
+  iid?: number;
+  /** Replace identifying data in the report with placeholders */
+  anonymize?: boolean;
+  /** Overrides the configured `approval_rule_summary` */
+  approvalRules?: boolean;
+  /** Minify JSON output */
+  compactJson?: boolean;
+  /** Max age (ms) of an open MR before the run fails; unset disables the check */
+  failIfOlderThan?: number;
+  /** Output format; if unset, chosen based on the environment */
+  format?: Format;
+  /** Overrides the configured `labels` filter */
+  labels?: string;
+  /** Append a list of whom to ping about blocked MRs */
+  nudge?: boolean;
+  /** Heading level of the report's top heading in Org output */
+  orgLevel?: number;
+  /** File to write a timing trace to */
+  profileTime?: string;
+  /** Overrides the configured `requires_issue` check */
+  requiresIssue?: boolean;
+  /** Only report MRs with one of the configured `reviewer_usernames` as reviewer */
+  reviewing?: boolean;
+  /** Only report MRs blocked for at least one of these reasons */
+  onlyBlockers?: Array<BlockerKind>;
+  /** List MRs using GitLab's trimmed `view=simple`, without blockers */
+  simple?: boolean;
+  /** Produce no output when there are no MRs to report */
+  skipIfEmpty?: boolean;
+  /** Address for `serve` to listen on */
+  bind?: { host: string; port: number };
+  /** How long (ms) `serve` reuses a fetched report */
+  cacheTtl?: number;
+  /** How often (ms) `tui` refreshes the report */
+  interval?: number;
+  /** How many weeks `trend` charts */
+  weeks?: number;
+}
+
+/**
+ * Parses command-line arguments into `Options`.
+ * Flag values may be given as `--flag value` or `--flag=value`.
+ *
+ * @param {string[]} argv - arguments, excluding node and script paths
+ * @returns {Options} parsed options
+ */
+export function parseArgs(argv: ReadonlyArray<string>): Options {
+  const options: Options = {};
+  const args = [...argv];
+
+  for (let arg = args.shift(); arg !== undefined; arg = args.shift()) {
+    const eq = arg.indexOf("=");
+    const flag = eq < 0 ? arg : arg.slice(0, eq);
+    const inline = eq < 0 ? undefined : arg.slice(eq + 1);
+
+    const value = (): string => {
+      const v = inline ?? args.shift();
+      if (v === undefined) throw new Error(`\`${flag}\` requires a value\n${USAGE}`);
+      return v;
+    };
+
+    switch (flag) {
+      case "--anonymize":
+        options.anonymize = true;
+        break;
+      case "--approval-rules":
+        options.approvalRules = true;
+        break;
+      case "--bind":
+        options.bind = parseBind(value());
+        break;
+      case "--cache-ttl":
+        options.cacheTtl = parseDuration(value());
+        break;
+      case "--compact-json":
+        options.compactJson = true;
+        break;
+      case "--fail-if-older-than":
+        options.failIfOlderThan = parseDuration(value());
+        break;
+      case "--format":
+        options.format = parseFormat(value());
+        break;
+      case "--only-blocker":
+        options.onlyBlockers = [
+          ...(options.onlyBlockers ?? []),
+          ...value().split(",").map(parseBlockerKind),
+        ];
+        break;
+      case "--interval":
+        options.interval = parseDuration(value());
+        break;
+      case "--labels":
+        options.labels = parseLabels(value());
+        break;
+      case "--nudge":
+        options.nudge = true;
+        break;
+      case "--org-level":
+        options.orgLevel = parseLevel(value());
+        break;
+      case "--profile-time":
+        options.profileTime = value();
+        break;
+      case "--requires-issue":
+        options.requiresIssue = true;
+        break;
+      case "--reviewing":
+        options.reviewing = true;
+        break;
+      case "--simple":
+        options.simple = true;
+        break;
+      case "--skip-if-empty":
+        options.skipIfEmpty = true;
+        break;
+      case "--weeks":
+        options.weeks = parseWeeks(value());
+        break;
+      default: {
+        if (options.command === "show" && options.iid === undefined) {
+          options.iid = parseIid(arg);
+          break;
+        }
+
+        const command = COMMANDS.find((c) => c === arg);
+        if (command === undefined || options.command !== undefined)
+          throw new Error(`unknown argument \`${arg}\`\n${USAGE}`);
+        options.command = command;
+      }
+    }
+  }
+
+  const markdown = !options.command && !options.simple && !options.format;
+  if (options.nudge && !(markdown || options.format === "markdown"))
+    throw new Error(`\`--nudge\` only applies to the markdown report`);
+
+  if (options.orgLevel !== undefined && options.format !== "org")
+    throw new Error(`\`--org-level\` only applies to \`--format org\``);
+
+  const json = options.format === "json" || options.command === "serve";
+  if (options.compactJson && !json)
+    throw new Error(`\`--compact-json\` only applies to \`--format json\` and \`serve\``);
+
+  if (options.command !== "tui" && options.interval !== undefined)
+    throw new Error(`\`--interval\` only applies to \`tui\``);
+
+  if (options.command !== "trend" && options.weeks !== undefined)
+    throw new Error(`\`--weeks\` only applies to \`trend\``);
+
+  if (options.command !== "serve" && (options.bind || options.cacheTtl !== undefined))
+    throw new Error(`\`--bind\` and \`--cache-ttl\` only apply to \`serve\``);
+
+  if (options.command === "show" && options.iid === undefined)
+    throw new Error(`\`show\` requires an MR iid\n${USAGE}`);
+
+  // Flags that only shape the one-shot report
+  const report_flags = [
+    options.failIfOlderThan !== undefined && "--fail-if-older-than",
+    options.format && "--format",
+    options.simple && "--simple",
+    options.skipIfEmpty && "--skip-if-empty",
+  ];
+
+  // `serve` never finishes, so there'd be no trace to write.
+  if (options.command === "serve")
+    rejectFlags("`serve`", [
+      ...report_flags,
+      options.profileTime && "--profile-time",
+    ]);
+
+  if (options.command === "tui") rejectFlags("`tui`", report_flags);
+
+  if (options.command === "trend")
+    rejectFlags("`trend`", [
+      ...report_flags,
+      options.anonymize && "--anonymize",
+      options.approvalRules && "--approval-rules",
+      options.onlyBlockers && "--only-blocker",
+      options.requiresIssue && "--requires-issue",
+      options.reviewing && "--reviewing",
+    ]);
+
+  if (options.command === "audit")
+    rejectFlags("`audit`", [
+      ...report_flags,
+      options.anonymize && "--anonymize",
+      options.approvalRules && "--approval-rules",
+      options.labels && "--labels",
+      options.onlyBlockers && "--only-blocker",
+      options.requiresIssue && "--requires-issue",
+      options.reviewing && "--reviewing",
+    ]);
+
+  if (options.command === "show")
+    rejectFlags("`show`", [
+      ...report_flags,
+      options.labels && "--labels",
+      options.onlyBlockers && "--only-blocker",
+      options.reviewing && "--reviewing",
+    ]);
+
+  if (options.simple)
+    rejectFlags("`--simple`", [
+      options.anonymize && "--anonymize",
+      options.format && "--format",
+      options.onlyBlockers && "--only-blocker",
+      options.requiresIssue && "--requires-issue",
+      options.reviewing && "--reviewing",
+      options.approvalRules && "--approval-rules",
+    ]);
+
+  return options;
+}
+
+function rejectFlags(
+  what: string,
+  flags: ReadonlyArray<string | false | undefined>
+): void {
+  const given = flags.filter(Boolean);
+  if (given.length > 0)
+    throw new Error(`${what} can't be combined with ${given.join(", ")}`);
+}
+
+function parseBind(s: string): { host: string; port: number } {
+  const colon = s.lastIndexOf(":");
+  const host = (colon < 0 ? "" : s.slice(0, colon)) || "127.0.0.1";
+  const port = Number(s.slice(colon + 1));
+
+  if (!Number.isInteger(port) || port < 0 || port > 65535)
+    throw new Error(`invalid bind address \`${s}\`: expected [<host>:]<port>`);
+
+  return { host, port };
+}
+
+function parseIid(s: string): number {
+  const iid = Number(s.replace(/^!/, ""));
+  if (!Number.isInteger(iid) || iid < 1)
+    throw new Error(`invalid MR iid \`${s}\`: expected e.g. 42 or !42`);
+  return iid;
+}
+
+// GitLab only recognizes the special values in this capitalization.
+function parseLabels(s: string): string {
+  const special = ["None", "Any"].find((v) => v.toLowerCase() === s.toLowerCase());
+  return special ?? s;
+}
+
+function parseLevel(s: string): number {
+  const level = Number(s);
+  if (!Number.isInteger(level) || level < 1)
+    throw new Error(`invalid heading level \`${s}\`: expected a positive integer`);
+  return level;
+}
+
+function parseWeeks(s: string): number {
+  const weeks = Number(s);
+  if (!Number.isInteger(weeks) || weeks < 1)
+    throw new Error(`invalid week count \`${s}\`: expected a positive integer`);
+  return weeks;
+}
+
+function parseBlockerKind(s: string): BlockerKind {
+  return parseChoice(BLOCKER_KINDS, s.trim(), "blocker kind");
+}
+
+function parseFormat(s: string): Format {
+  return parseChoice(FORMATS, s, "format");
+}
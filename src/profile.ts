@@ -0,0 +1,125 @@
+import { writeFile } from "fs/promises";
+import { performance } from "perf_hooks";
+
+/**
+ * A completed span, in the Chrome trace event format
+ *
+ * https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+ */
+interface TraceEvent {
+  readonly name: string;
+  readonly ph: "X";
+  /** Start, in microseconds since the program started */
+  readonly ts: number;
+  /** Duration, in microseconds */
+  readonly dur: number;
+  readonly pid: number;
+  readonly tid: number;
+  readonly args?: Record<string, string | number>;
+}
+
+/**
+ * Totals for all spans of a phase
+ */
+export interface PhaseSummary {
+  readonly count: number;
+  readonly total_ms: number;
+  readonly max_ms: number;
+}
+
+const microseconds = (ms: number) => Math.round(ms * 1000);
+
+/**
+ * Records how long each phase of a run takes, for `--profile-time`.
+ *
+ * Concurrent spans, such as requests for different MRs, are recorded on
+ * separate lanes (trace "threads") so that trace viewers don't mistake them
+ * for nested calls.
+ */
+export class Profiler {
+  #enabled = false;
+  readonly #events: Array<TraceEvent> = [];
+  readonly #busy: Array<boolean> = [];
+
+  /**
+   * Starts recording. Until then, `time` only runs the given function.
+   */
+  enable(): void {
+    this.#enabled = true;
+  }
+
+  /**
+   * Runs `f`, recording how long it took as a span of `phase`.
+   *
+   * @param {string} phase - name of the phase, e.g. `page`
+   * @param {() => Promise<T>} f - work to time
+   * @param {Record<string, string | number>} args - optional details to
+   *   attach to the span, e.g. the MR iid
+   * @returns {Promise<T>} the result of `f`
+   */
+  async time<T>(
+    phase: string,
+    f: () => Promise<T>,
+    args?: Record<string, string | number>
+  ): Promise<T> {
+    if (!this.#enabled) return f();
+
+    const free = this.#busy.indexOf(false);
+    const lane = free < 0 ? this.#busy.length : free;
+    this.#busy[lane] = true;
+
+    const start = performance.now();
+    try {
+      return await f();
+    } finally {
+      this.#busy[lane] = false;
+      this.#events.push({
+        name: phase,
+        ph: "X",
+        ts: microseconds(start),
+        dur: microseconds(performance.now() - start),
+        pid: 1,
+        tid: lane + 1,
+        ...(args ? { args } : {}),
+      });
+    }
+  }
+
+  /**
+   * Totals the recorded spans by phase.
+   *
+   * @returns {Record<string, PhaseSummary>} span count and durations per phase
+   */
+  summary(): Record<string, PhaseSummary> {
+    const phases: Record<string, PhaseSummary> = {};
+    for (const { name, dur } of this.#events) {
+      const ms = dur / 1000;
+      const phase = phases[name] ?? { count: 0, total_ms: 0, max_ms: 0 };
+      phases[name] = {
+        count: phase.count + 1,
+        total_ms: phase.total_ms + ms,
+        max_ms: Math.max(phase.max_ms, ms),
+      };
+    }
+    return phases;
+  }
+
+  /**
+   * Writes the recorded spans as a Chrome trace, which can be opened in e.g.
+   * https://ui.perfetto.dev or https://www.speedscope.app, along with a
+   * per-phase summary.
+   *
+   * @param {string} path - file to write
+   */
+  async save(path: string): Promise<void> {
+    const trace = {
+      displayTimeUnit: "ms",
+      phases: this.summary(),
+      traceEvents: this.#events,
+    };
+    await writeFile(path, JSON.stringify(trace, null, 2));
+  }
+}
+
+/** Profiler shared by the whole run; disabled unless `--profile-time` is given */
+export const profiler = new Profiler();
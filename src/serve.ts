@@ -0,0 +1,112 @@
+import { ServerResponse, createServer } from "http";
+
+import { Report, Section, formatHtml, formatJson } from "./format";
+import { log } from "./util";
+
+export interface ServeOptions {
+  readonly host: string;
+  readonly port: number;
+  /** How long (ms) a fetched report is reused before fetching a new one */
+  readonly cacheTtl: number;
+  /** Serve minified JSON */
+  readonly compactJson?: boolean;
+  readonly label_emoji?: Record<string, string>;
+  /** Order of the sections in `/report.html` */
+  readonly sectionOrder?: ReadonlyArray<Section>;
+  readonly timezone?: string;
+}
+
+interface CachedReport {
+  readonly at: number;
+  readonly report: Promise<Report>;
+}
+
+/**
+ * Runs an HTTP server that fetches and renders the report on demand.
+ *
+ * Serves `/report.json` and `/report.html`. Reports are cached for
+ * `cacheTtl`, and concurrent requests share a single in-flight fetch.
+ *
+ * @param {() => Promise<Report>} fetchReport - fetches a fresh report
+ * @param {ServeOptions} options - server options
+ * @returns {Promise<void>} resolves once the server is listening; rejects if
+ *   it can't listen, e.g. because the port is in use
+ */
+export function serve(
+  fetchReport: () => Promise<Report>,
+  options: ServeOptions
+): Promise<void> {
+  let cached: CachedReport | undefined;
+
+  const report = (): Promise<Report> => {
+    if (!cached || Date.now() - cached.at >= options.cacheTtl) {
+      const entry = { at: Date.now(), report: fetchReport() };
+      // Failed fetches shouldn't be served from cache.
+      entry.report.catch(() => {
+        if (cached === entry) cached = undefined;
+      });
+      cached = entry;
+    }
+    return cached.report;
+  };
+
+  const server = createServer((req, res) => {
+    const { pathname } = new URL(req.url ?? "/", "http://localhost");
+    log(`${req.method} ${pathname}`);
+
+    if (req.method !== "GET") {
+      return send(res, 405, "text/plain", "method not allowed\n");
+    }
+
+    switch (pathname) {
+      case "/report.json":
+        return respond(res, report(), "application/json", (r) =>
+          formatJson(r, options.compactJson)
+        );
+      case "/report.html":
+        return respond(res, report(), "text/html; charset=utf-8", (r) =>
+          formatHtml(r, options.label_emoji, options.timezone, options.sectionOrder)
+        );
+      default:
+        return send(res, 404, "text/plain", "not found\n");
+    }
+  });
+
+  const address = `${options.host}:${options.port}`;
+  return new Promise((resolve, reject) => {
+    const failed = (err: Error) =>
+      reject(new Error(`Unable to serve on ${address}: ${err.message}`));
+
+    server.once("error", failed);
+    server.listen(options.port, options.host, () => {
+      server.off("error", failed);
+      server.on("error", (err) => log(`Server error: ${err.message}`));
+      log(`Serving http://${address}/report.html`);
+      resolve();
+    });
+  });
+}
+
+function respond(
+  res: ServerResponse,
+  report: Promise<Report>,
+  type: string,
+  render: (report: Report) => string
+): void {
+  report
+    .then((r) => send(res, 200, type, render(r)))
+    .catch((err) => {
+      log(`Unable to fetch report: ${err.message}`);
+      send(res, 502, "text/plain", `${err.message}\n`);
+    });
+}
+
+function send(
+  res: ServerResponse,
+  status: number,
+  type: string,
+  body: string
+): void {
+  res.writeHead(status, { "content-type": type });
+  res.end(body);
+}
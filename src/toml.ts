@@ -0,0 +1,84 @@
+type Table = Readonly<Record<string, unknown>>;
+
+const BARE_KEY = /^[A-Za-z0-9_-]+$/;
+
+/**
+ * Serializes a table as TOML.
+ *
+ * TOML has no null, so `null` and `undefined` values are omitted. Objects
+ * become tables, and arrays of objects become arrays of tables. TOML can't
+ * represent a top-level array, so callers must wrap one in a table.
+ *
+ * @param {Record<string, unknown>} table - value to serialize, e.g. a `Report`
+ * @returns {string} TOML document
+ */
+export function toToml(table: object): string {
+  const output: Array<string> = [];
+  writeTable(output, [], table as Table);
+  return output.join("");
+}
+
+function isTable(v: unknown): v is Table {
+  return typeof v === "object" && v !== null && !Array.isArray(v);
+}
+
+function isTableArray(v: unknown): v is ReadonlyArray<Table> {
+  return Array.isArray(v) && v.length > 0 && v.every(isTable);
+}
+
+function writeTable(
+  output: Array<string>,
+  path: ReadonlyArray<string>,
+  table: Table
+): void {
+  const entries = Object.entries(table).filter(
+    ([, v]) => v !== undefined && v !== null
+  );
+
+  // Plain keys must come before any subtables, or they'd belong to them.
+  for (const [k, v] of entries) {
+    if (!isTable(v) && !isTableArray(v)) output.push(`${key(k)} = ${value(v)}\n`);
+  }
+
+  for (const [k, v] of entries) {
+    const subpath = [...path, k];
+    const header = subpath.map(key).join(".");
+
+    if (isTable(v)) {
+      output.push(`\n[${header}]\n`);
+      writeTable(output, subpath, v);
+    } else if (isTableArray(v)) {
+      for (const item of v) {
+        output.push(`\n[[${header}]]\n`);
+        writeTable(output, subpath, item);
+      }
+    }
+  }
+}
+
+function key(k: string): string {
+  return BARE_KEY.test(k) ? k : JSON.stringify(k);
+}
+
+function value(v: unknown): string {
+  if (Array.isArray(v))
+    return `[${v.filter((x) => x !== undefined && x !== null).map(value).join(", ")}]`;
+
+  if (isTable(v)) {
+    const pairs = Object.entries(v)
+      .filter(([, x]) => x !== undefined && x !== null)
+      .map(([k, x]) => `${key(k)} = ${value(x)}`);
+    return `{ ${pairs.join(", ")} }`;
+  }
+
+  if (typeof v === "number") {
+    if (Number.isNaN(v)) return "nan";
+    if (!Number.isFinite(v)) return v > 0 ? "inf" : "-inf";
+    return v.toString();
+  }
+
+  if (typeof v === "boolean") return v.toString();
+
+  // JSON's string escapes are all valid in TOML basic strings.
+  return JSON.stringify(String(v));
+}
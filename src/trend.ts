@@ -0,0 +1,109 @@
+import { Author, MergedMergeRequest } from "./gitlab";
+import { formatTimestamp } from "./util";
+
+const WEEK = 7 * 24 * 60 * 60 * 1000;
+const SPARKS = "▂▃▄▅▆▇█";
+
+/**
+ * Weekly merge counts, oldest week first
+ */
+export interface Trend {
+  /** Start of each week */
+  readonly weeks: ReadonlyArray<Date>;
+  /** Counts for the whole team */
+  readonly team: ReadonlyArray<number>;
+  /** Counts for each author, by friendly name or GitLab username */
+  readonly authors: ReadonlyMap<string, ReadonlyArray<number>>;
+}
+
+/**
+ * Counts merges in each of the last `weeks` weeks, for the team and for each
+ * author. Weeks are the 7-day periods ending `now`.
+ *
+ * @param {Array<MergedMergeRequest>} mrs - merged MRs
+ * @param {number} weeks - how many weeks to count
+ * @param {Record<string, number>} authors - friendly names to GitLab user ids
+ * @param {number} now - end of the latest week, in ms since the epoch
+ * @returns {Trend} weekly counts
+ */
+export function weeklyMerges(
+  mrs: ReadonlyArray<MergedMergeRequest>,
+  weeks: number,
+  authors: Readonly<Record<string, number>>,
+  now = Date.now()
+): Trend {
+  const names = new Map(Object.entries(authors).map(([name, id]) => [id, name]));
+  const name = (user: Author) => names.get(user.id) ?? user.username;
+
+  const team = new Array<number>(weeks).fill(0);
+  const by_author = new Map<string, Array<number>>();
+
+  for (const mr of mrs) {
+    if (!mr.merged_at) continue;
+
+    const ago = Math.floor((now - Date.parse(mr.merged_at)) / WEEK);
+    if (ago < 0 || ago >= weeks) continue;
+    const week = weeks - 1 - ago;
+
+    const counts = by_author.get(name(mr.author)) ?? new Array<number>(weeks).fill(0);
+    counts[week] = (counts[week] ?? 0) + 1;
+    by_author.set(name(mr.author), counts);
+    team[week] = (team[week] ?? 0) + 1;
+  }
+
+  return {
+    weeks: Array.from({ length: weeks }, (_, i) => new Date(now - (weeks - i) * WEEK)),
+    team,
+    authors: new Map([...by_author].sort(([a], [b]) => a.localeCompare(b))),
+  };
+}
+
+/**
+ * Draws counts as a sparkline, scaled to the largest count. Weeks without
+ * merges are left blank.
+ *
+ * @param {Array<number>} counts - counts to draw
+ * @returns {string} one character per count
+ */
+export function sparkline(counts: ReadonlyArray<number>): string {
+  const max = Math.max(...counts, 1);
+  return counts
+    .map((n) =>
+      n === 0 ? " " : SPARKS[Math.ceil((n / max) * SPARKS.length) - 1] ?? "█"
+    )
+    .join("");
+}
+
+/**
+ * Formats a `Trend` as a plain text table with a sparkline per row.
+ *
+ * @param {string} target_branch - branch the MRs were merged into
+ * @param {Trend} trend - weekly counts
+ * @param {string} timezone - IANA time zone for the start date; defaults to UTC
+ * @returns {string} formatted text
+ */
+export function formatTrend(
+  target_branch: string,
+  trend: Trend,
+  timezone?: string
+): string {
+  const rows: Array<[string, ReadonlyArray<number>]> = [
+    ["team", trend.team],
+    ...trend.authors,
+  ];
+  const width = Math.max(...rows.map(([name]) => name.length));
+  const [start] = trend.weeks;
+  // Just the date, from e.g. "2022-04-04 09:30 CEST"
+  const since = start ? formatTimestamp(start, timezone).slice(0, 10) : "";
+
+  const lines = rows.map(([name, counts]) => {
+    const total = counts.reduce((sum, n) => sum + n, 0);
+    const weekly = counts.map((n) => n.toString().padStart(3)).join("");
+    return `${name.padEnd(width)}  ${sparkline(counts)} ${weekly}  total ${total}\n`;
+  });
+
+  return [
+    `Merges per week into ${target_branch} since ${since}, oldest first:\n\n`,
+    ...lines,
+  ].join("");
+}
@@ -0,0 +1,305 @@
+import { execFile } from "child_process";
+import { Key, emitKeypressEvents } from "readline";
+import { platform, stdin, stdout } from "process";
+
+import { Report } from "./format";
+import { MergeRequest } from "./gitlab";
+import { formatTimestamp, log } from "./util";
+
+export interface TuiOptions {
+  /** How often (ms) to refresh the report; unset refreshes only on `r` */
+  readonly interval?: number;
+  /** Marks MRs that are new or newly blocked since the previous refresh */
+  readonly changeMarker?: string;
+  /** IANA time zone for the refresh time; defaults to UTC */
+  readonly timezone?: string;
+}
+
+interface Row {
+  readonly mr: MergeRequest;
+  readonly ready: boolean;
+  readonly changed: boolean;
+  readonly text: string;
+}
+
+const CHANGE_MARKER = "*";
+
+const HELP = "↑/↓ move  / filter  enter open  r refresh  q quit";
+
+// Lines taken by the header (title, filter) and footer (details, status, help).
+const HEADER_LINES = 2;
+const FOOTER_LINES = 3;
+
+const bold = (s: string) => `\x1b[1m${s}\x1b[0m`;
+const dim = (s: string) => `\x1b[2m${s}\x1b[0m`;
+const inverse = (s: string) => `\x1b[7m${s}\x1b[0m`;
+
+/**
+ * Checks whether every character of `query` appears in `text`, in order,
+ * ignoring case; e.g. `fxlog` matches "fix: login redirect".
+ *
+ * @param {string} query - what the user typed
+ * @param {string} text - text to match against
+ * @returns {boolean} true if `text` matches
+ */
+export function fuzzyMatch(query: string, text: string): boolean {
+  const haystack = text.toLowerCase();
+  let i = 0;
+  for (const c of query.toLowerCase()) {
+    i = haystack.indexOf(c, i) + 1;
+    if (i === 0) return false;
+  }
+  return true;
+}
+
+/**
+ * Runs an interactive dashboard of the report in the terminal, until the
+ * user quits. Requires stdin and stdout to be a TTY.
+ *
+ * While it runs, `log` is silenced so that it doesn't draw over the screen.
+ *
+ * @param {() => Promise<Report>} fetchReport - fetches a fresh report
+ * @param {TuiOptions} options - dashboard options
+ * @returns {Promise<void>} resolves when the user quits
+ */
+export function tui(
+  fetchReport: () => Promise<Report>,
+  options: TuiOptions
+): Promise<void> {
+  let report: Report | undefined;
+  // MRs that are new or newly blocked since the previous refresh
+  let changed = new Set<number>();
+  let loading = false;
+  let status = "";
+  let filter = "";
+  let filtering = false;
+  let selected = 0;
+  let top = 0;
+
+  const rows = (): Array<Row> => {
+    if (!report) return [];
+
+    const marker = options.changeMarker ?? CHANGE_MARKER;
+    const all = [
+      ...report.ready.map((mr) => ({ mr, ready: true })),
+      ...report.blocked.map((mr) => ({ mr, ready: false })),
+    ].map(({ mr, ready }) => ({
+      mr,
+      ready,
+      changed: changed.has(mr.iid),
+      text: [
+        changed.has(mr.iid) ? marker : " ".repeat(marker.length),
+        ready ? "✓" : "✗",
+        `!${mr.iid} ${mr.title} (${mr.author.username})`,
+      ].join(" "),
+    }));
+
+    return filter ? all.filter((row) => fuzzyMatch(filter, row.text)) : all;
+  };
+
+  const draw = () => {
+    const width = stdout.columns || 80;
+    const height = stdout.rows || 24;
+    const body = Math.max(height - HEADER_LINES - FOOTER_LINES, 1);
+    const fit = (s: string) => (s.length > width ? `${s.slice(0, width - 1)}…` : s);
+
+    const list = rows();
+    selected = Math.max(Math.min(selected, list.length - 1), 0);
+    if (selected < top) top = selected;
+    if (selected >= top + body) top = selected - body + 1;
+
+    const lines: Array<string> = [];
+    const title = report
+      ? `Open MRs against ${report.target_branch}: ` +
+        `${report.ready.length} ready, ${report.blocked.length} blocked`
+      : "Open MRs";
+    lines.push(bold(fit(title)));
+    lines.push(filtering || filter ? fit(`/${filter}${filtering ? "▏" : ""}`) : "");
+
+    for (let i = top; i < top + body; i++) {
+      const row = list[i];
+      if (!row) {
+        lines.push("");
+      } else {
+        const text = row.changed ? bold(fit(row.text)) : fit(row.text);
+        lines.push(i === selected ? inverse(text) : text);
+      }
+    }
+
+    const current = list[selected];
+    const details = current
+      ? [
+          current.mr.blockers.map((b) => b.message).join(", ") || "ready to merge",
+          ...current.mr.notes,
+        ].join(", ")
+      : report
+      ? "no matching MRs"
+      : "";
+    lines.push(fit(details));
+    lines.push(dim(fit(loading ? "Refreshing…" : status)));
+    lines.push(dim(fit(HELP)));
+
+    stdout.write(`\x1b[H${lines.map((line) => `${line}\x1b[K`).join("\r\n")}`);
+  };
+
+  const refresh = () => {
+    if (loading) return;
+    loading = true;
+    draw();
+
+    fetchReport()
+      .then(
+        (fresh) => {
+          changed = report ? changes(report, fresh) : new Set();
+          report = fresh;
+          status = `Updated ${formatTimestamp(Date.now(), options.timezone)}`;
+        },
+        (e) => {
+          status = `Refresh failed: ${e instanceof Error ? e.message : e}`;
+        }
+      )
+      .finally(() => {
+        loading = false;
+        draw();
+      });
+  };
+
+  const open = (mr: MergeRequest) => {
+    openUrl(mr.web_url)
+      .then(
+        () => {
+          status = `Opened !${mr.iid}`;
+        },
+        (e) => {
+          status = `Couldn't open !${mr.iid}: ${e instanceof Error ? e.message : e}`;
+        }
+      )
+      .finally(draw);
+  };
+
+  return new Promise((resolve) => {
+    const quit = () => {
+      if (timer) clearInterval(timer);
+      stdin.off("keypress", onKey);
+      stdout.off("resize", draw);
+      stdin.setRawMode(false);
+      stdin.pause();
+      // Show the cursor and leave the alternate screen.
+      stdout.write("\x1b[?25h\x1b[?1049l");
+      log.quiet = false;
+      resolve();
+    };
+
+    const onKey = (str: string | undefined, key: Key): void => {
+      if (key.ctrl && key.name === "c") return quit();
+
+      if (filtering) {
+        if (key.name === "return") {
+          filtering = false;
+        } else if (key.name === "escape") {
+          filtering = false;
+          filter = "";
+        } else if (key.name === "backspace") {
+          filter = filter.slice(0, -1);
+        } else if (str && !key.ctrl && !key.meta && str >= " ") {
+          filter += str;
+          selected = 0;
+        }
+        return draw();
+      }
+
+      const page = Math.max((stdout.rows || 24) - HEADER_LINES - FOOTER_LINES, 1);
+      switch (key.name ?? str) {
+        case "q":
+          return quit();
+        case "up":
+        case "k":
+          selected--;
+          break;
+        case "down":
+        case "j":
+          selected++;
+          break;
+        case "pageup":
+          selected -= page;
+          break;
+        case "pagedown":
+          selected += page;
+          break;
+        case "home":
+          selected = 0;
+          break;
+        case "end":
+          selected = rows().length - 1;
+          break;
+        case "escape":
+          filter = "";
+          break;
+        case "r":
+          return refresh();
+        case "return": {
+          const row = rows()[selected];
+          if (row) open(row.mr);
+          break;
+        }
+        default:
+          if (str === "/") filtering = true;
+      }
+      draw();
+    };
+
+    log.quiet = true;
+    emitKeypressEvents(stdin);
+    stdin.setRawMode(true);
+    stdin.resume();
+    stdin.on("keypress", onKey);
+    stdout.on("resize", draw);
+    // Switch to the alternate screen, so the dashboard doesn't clobber
+    // scrollback, and hide the cursor.
+    stdout.write("\x1b[?1049h\x1b[?25l\x1b[2J");
+
+    const timer =
+      options.interval !== undefined ? setInterval(refresh, options.interval) : undefined;
+    refresh();
+  });
+}
+
+/**
+ * Finds the MRs that appeared or became blocked between two reports.
+ *
+ * @param {Report} previous - the earlier report
+ * @param {Report} current - the later report
+ * @returns {Set<number>} iids of the changed MRs
+ */
+function changes(previous: Report, current: Report): Set<number> {
+  const was_ready = new Set(previous.ready.map((mr) => mr.iid));
+  const was_blocked = new Set(previous.blocked.map((mr) => mr.iid));
+
+  return new Set([
+    ...current.ready
+      .filter((mr) => !was_ready.has(mr.iid) && !was_blocked.has(mr.iid))
+      .map((mr) => mr.iid),
+    ...current.blocked
+      .filter((mr) => !was_blocked.has(mr.iid))
+      .map((mr) => mr.iid),
+  ]);
+}
+
+/**
+ * Opens a URL in the user's browser.
+ *
+ * @param {string} url - URL to open
+ * @returns {Promise<void>} resolves once the browser has been launched
+ */
+function openUrl(url: string): Promise<void> {
+  const [command, args]: [string, Array<string>] =
+    platform === "darwin"
+      ? ["open", [url]]
+      : platform === "win32"
+      ? ["cmd", ["/c", "start", "", url]]
+      : ["xdg-open", [url]];
+
+  return new Promise((resolve, reject) => {
+    execFile(command, args, (err) => (err ? reject(err) : resolve()));
+  });
+}
@@ -1,40 +1,96 @@
-import { MergeRequest } from './gitlab';
+import { Author, MergeRequest } from './gitlab';
 
 /**
- * Logs arguments to stderr.
+ * Logs arguments to stderr, unless `log.quiet` is set.
  * Includes a timestamp of milliseconds since program start.
  *
  * @param {...*} ...args - values to log
  */
 export function log(...args: unknown[]): void {
+  if (log.quiet) return;
   const delta = Date.now() - log.start;
   console.warn(`[${delta.toString().padStart(5)}ms]`, ...args);
 }
 log.start = Date.now();
+log.quiet = false;
 
 /**
- * Formats a `MergeRequest` for display in Slack-style markdown.
+ * Replaces identifying author data and URLs with placeholders so a report can
+ * be shared publicly. Pseudonyms are stable for a given author within a run.
  *
- * @param {string} header - used as section header
- * @param {Array<MergeRequest>} mrs - list of MRs to display
- * @returns {string} Slack-formatted text
+ * The copies are built from an allow-list of fields, rather than spreading
+ * `...mr`, so that raw GitLab data such as descriptions, assignees or avatar
+ * URLs never reaches JSON or TOML output.
+ *
+ * @param {Array<MergeRequest>} mrs - MRs to anonymize
+ * @returns {Array<MergeRequest>} anonymized copies of the MRs
  */
-export function formatMRs(header: string, mrs: ReadonlyArray<MergeRequest>): string {
-  const output = [`* *${header}*\n`];
+export function anonymize(mrs: ReadonlyArray<MergeRequest>): Array<MergeRequest> {
+  const pseudonyms = new Map<number, number>();
+  const anonymous = (user: Author): Author => {
+    const n = pseudonyms.get(user.id) ?? pseudonyms.size + 1;
+    pseudonyms.set(user.id, n);
+    return { id: n, name: `author${n}`, username: `author${n}` };
+  };
 
-  for (const mr of mrs) {
-    output.push(`    * [${mr.title}](${mr.web_url}) (${mr.author.username})\n`);
+  return mrs.map((mr) => ({
+    approvals_needed: mr.approvals_needed,
+    author: anonymous(mr.author),
+    blockers: mr.blockers,
+    blocking_discussions_resolved: mr.blocking_discussions_resolved,
+    created_at: mr.created_at,
+    draft: mr.draft,
+    has_conflicts: mr.has_conflicts,
+    iid: mr.iid,
+    informal_approvals: mr.informal_approvals,
+    labels: mr.labels,
+    label_details: mr.label_details,
+    last_activity_at: mr.last_activity_at,
+    merge_status: mr.merge_status,
+    metadata: mr.metadata,
+    notes: mr.notes,
+    pending_approvers: mr.pending_approvers?.map(anonymous),
+    pipeline: mr.pipeline && {
+      id: mr.pipeline.id,
+      status: mr.pipeline.status,
+      web_url: `https://gitlab.example.com/pipelines/${mr.pipeline.id}`,
+    },
+    review_rounds: mr.review_rounds,
+    reviewers: mr.reviewers?.map(anonymous),
+    // Branch names often include their author's username.
+    source_branch: `branch-${mr.iid}`,
+    state: mr.state,
+    target_branch: mr.target_branch,
+    title: mr.title,
+    updated_at: mr.updated_at,
+    web_url: `https://gitlab.example.com/merge_requests/${mr.iid}`,
+  }));
+}
 
-    if (mr.labels.length > 0) {
-      output.push(`        * Labels: ${mr.labels.join(", ")}\n`);
-    }
+/**
+ * Maps `f` over `xs` with at most `limit` calls in flight at once.
+ *
+ * @param {Array<T>} xs - values to map
+ * @param {number} limit - maximum number of concurrent calls
+ * @param {(x: T) => Promise<U>} f - async mapping function
+ * @returns {Promise<Array<U>>} results, in the same order as `xs`
+ */
+export async function mapLimit<T, U>(
+  xs: ReadonlyArray<T>,
+  limit: number,
+  f: (x: T) => Promise<U>
+): Promise<Array<U>> {
+  const results = new Array<U>(xs.length);
+  let next = 0;
 
-    if (mr.blockers.length > 0) {
-      output.push(`        * ${mr.blockers.join(", ")}\n`);
+  const worker = async () => {
+    for (let i = next++; i < xs.length; i = next++) {
+      results[i] = await f(xs[i] as T);
     }
-  }
+  };
 
-  return output.join("");
+  await Promise.all(Array.from({ length: Math.min(limit, xs.length) }, worker));
+  return results;
 }
 
 export function groupBy<T, K, F extends (arg0: T) => K>(f: F, xs: ReadonlyArray<T>): Map<K, Array<T>> {
@@ -53,3 +109,91 @@ export function groupBy<T, K, F extends (arg0: T) => K>(f: F, xs: ReadonlyArray<
   return m;
 }
 
+
+/**
+ * Parses one of a fixed set of string values, e.g. for a command-line flag.
+ *
+ * @param {T[]} choices - valid values
+ * @param {string} s - value to parse
+ * @param {string} what - name of the value, for error messages
+ * @returns {T} the matching choice
+ */
+export function parseChoice<T extends string>(
+  choices: ReadonlyArray<T>,
+  s: string,
+  what: string
+): T {
+  const choice = choices.find((c) => c === s);
+  if (choice === undefined)
+    throw new Error(`unknown ${what} \`${s}\`: expected one of ${choices.join(", ")}`);
+  return choice;
+}
+
+const DURATION_UNITS: Record<string, number> = {
+  s: 1000,
+  m: 60 * 1000,
+  h: 60 * 60 * 1000,
+  d: 24 * 60 * 60 * 1000,
+  w: 7 * 24 * 60 * 60 * 1000,
+};
+
+/**
+ * Parses a duration such as `30s`, `90m`, `36h`, `3d` or `2w`.
+ *
+ * @param {string} s - duration string
+ * @returns {number} duration in milliseconds
+ */
+export function parseDuration(s: string): number {
+  const match = /^(\d+)([smhdw])$/.exec(s.trim());
+  const unit = match?.[2] !== undefined ? DURATION_UNITS[match[2]] : undefined;
+
+  if (!match || unit === undefined)
+    throw new Error(`invalid duration \`${s}\`: expected e.g. 30s, 90m, 36h, 3d or 2w`);
+
+  return Number(match[1]) * unit;
+}
+
+/**
+ * Formats a timestamp in the given timezone, e.g. `2022-04-01 10:12 GMT-7`.
+ *
+ * @param {string | number | Date} timestamp - time to format
+ * @param {string} timeZone - IANA timezone name, e.g. `America/Los_Angeles`
+ * @returns {string} the formatted timestamp
+ * @throws {RangeError} if `timeZone` isn't a known timezone
+ */
+export function formatTimestamp(
+  timestamp: string | number | Date,
+  timeZone = "UTC"
+): string {
+  const format = new Intl.DateTimeFormat("en-GB", {
+    timeZone,
+    year: "numeric",
+    month: "2-digit",
+    day: "2-digit",
+    hour: "2-digit",
+    minute: "2-digit",
+    hour12: false,
+    timeZoneName: "short",
+  });
+  const parts = Object.fromEntries(
+    format.formatToParts(new Date(timestamp)).map((p) => [p.type, p.value])
+  );
+
+  return `${parts["year"]}-${parts["month"]}-${parts["day"]} ${parts["hour"]}:${parts["minute"]} ${parts["timeZoneName"]}`;
+}
+
+/**
+ * Formats a duration as its two most significant units, e.g. `3d 4h`.
+ *
+ * @param {number} ms - duration in milliseconds
+ * @returns {string} human-readable duration
+ */
+export function formatDuration(ms: number): string {
+  const minutes = Math.floor(ms / (60 * 1000));
+  const days = Math.floor(minutes / (24 * 60));
+  const hours = Math.floor(minutes / 60) % 24;
+
+  if (days > 0) return `${days}d ${hours}h`;
+  if (hours > 0) return `${hours}h ${minutes % 60}m`;
+  return `${minutes}m`;
+}
@@ -0,0 +1,45 @@
+import { strict as assert } from "assert";
+import { afterEach, test } from "node:test";
+
+import { DEFAULT_EXIT_CODES, ExitStatus } from "../src/exit";
+
+// Raising a condition sets the test process's own exit status.
+afterEach(() => {
+  process.exitCode = 0;
+});
+
+test("no conditions exit with 0", () => {
+  assert.equal(new ExitStatus().code(), 0);
+});
+
+test("the most severe condition wins, whatever the raise order", () => {
+  const status = new ExitStatus();
+  status.raise("empty");
+  status.raise("sla_violation");
+  assert.equal(status.code(), DEFAULT_EXIT_CODES.sla_violation);
+
+  status.raise("api_error");
+  assert.equal(status.code(), DEFAULT_EXIT_CODES.api_error);
+});
+
+test("a configured code of 0 disables the condition", () => {
+  const status = new ExitStatus();
+  status.configure({ sla_violation: 0, blocked: 5 });
+  status.raise("sla_violation");
+  assert.equal(status.code(), 0);
+
+  status.raise("empty");
+  status.raise("blocked");
+  assert.equal(status.code(), 5);
+});
+
+test("configure rejects unknown conditions", () => {
+  assert.throws(
+    () => new ExitStatus().configure({ stale: 2 }),
+    /unknown `exit_codes` condition `stale`/
+  );
+});
+
+test("configure rejects out-of-range codes", () => {
+  assert.throws(() => new ExitStatus().configure({ empty: 256 }), /expected 0-255/);
+});
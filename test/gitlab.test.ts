@@ -0,0 +1,112 @@
+import { strict as assert } from "assert";
+import { test } from "node:test";
+
+import {
+  GitLab,
+  MERGE_STATUSES,
+  MRApprovalStatus,
+  MR_STATES,
+  MergeRequest,
+  isDraft,
+  parseApprovals,
+  parseMRState,
+  parseMergeStatus,
+  requestHeaders,
+} from "../src/gitlab";
+
+const mr = (fields: Partial<MergeRequest>): MergeRequest =>
+  ({ title: "fix: login redirect", ...fields } as MergeRequest);
+
+test("isDraft prefers draft over work_in_progress", () => {
+  assert.equal(isDraft(mr({ draft: true, work_in_progress: false })), true);
+  assert.equal(isDraft(mr({ draft: false, work_in_progress: true })), false);
+});
+
+test("isDraft prefers draft over the title", () => {
+  assert.equal(isDraft(mr({ draft: false, title: "Draft: fix login" })), false);
+  assert.equal(isDraft(mr({ draft: true })), true);
+});
+
+test("isDraft prefers work_in_progress over the title", () => {
+  assert.equal(isDraft(mr({ work_in_progress: false, title: "WIP: fix login" })), false);
+  assert.equal(isDraft(mr({ work_in_progress: true })), true);
+});
+
+test("isDraft falls back to the title", () => {
+  for (const title of ["Draft: fix", "[Draft] fix", "(WIP) fix", "wip: fix"])
+    assert.equal(isDraft(mr({ title })), true, title);
+  assert.equal(isDraft(mr({ title: "fix: drafting tool" })), false);
+});
+
+test("isDraft reads each API version's payload shape", () => {
+  // Current APIs send both fields; older ones only `work_in_progress`, and
+  // the oldest neither.
+  const payloads = [
+    '{"title": "Draft: fix", "draft": true, "work_in_progress": true}',
+    '{"title": "fix", "draft": false, "work_in_progress": false}',
+    '{"title": "WIP: fix", "work_in_progress": true}',
+    '{"title": "fix", "work_in_progress": false}',
+    '{"title": "WIP: fix"}',
+    '{"title": "fix"}',
+  ];
+  const drafts = payloads.map((payload) => isDraft(JSON.parse(payload)));
+  assert.deepEqual(drafts, [true, false, true, false, true, false]);
+});
+
+test("parseApprovals keeps a null approvals_left as unknown", () => {
+  const approvals = parseApprovals(
+    JSON.parse('{"approvals_left": null, "approved_by": [], "suggested_approvers": []}')
+  );
+  assert.equal(approvals.approvals_left, null);
+});
+
+test("parseApprovals lists suggested approvers who haven't approved", () => {
+  const alice = { id: 1, name: "Alice", username: "alice" };
+  const bob = { id: 2, name: "Bob", username: "bob" };
+  const approvals = parseApprovals({
+    approvals_left: 1,
+    approved_by: [{ user: alice }],
+    suggested_approvers: [alice, bob],
+  } as MRApprovalStatus);
+  assert.equal(approvals.approvals_left, 1);
+  assert.deepEqual(approvals.pending_approvers, [bob]);
+});
+
+test("parseMRState round-trips the API's state spellings", () => {
+  for (const state of MR_STATES) {
+    const { state: parsed } = JSON.parse(JSON.stringify({ state }));
+    assert.equal(parseMRState(parsed), state);
+  }
+});
+
+test("parseMRState lists the valid states for other spellings", () => {
+  assert.throws(
+    () => parseMRState("open"),
+    /unknown MR state `open`: expected one of opened, closed, locked, merged/
+  );
+});
+
+test("parseMergeStatus round-trips the API's merge status spellings", () => {
+  for (const merge_status of MERGE_STATUSES) {
+    const { merge_status: parsed } = JSON.parse(JSON.stringify({ merge_status }));
+    assert.equal(parseMergeStatus(parsed), merge_status);
+  }
+  assert.throws(() => parseMergeStatus("Can_Be_Merged"), /expected one of unchecked/);
+});
+
+test("GitLab can be constructed without a token", () => {
+  const warn = console.warn;
+  console.warn = () => undefined;
+  try {
+    assert.doesNotThrow(() => new GitLab({ authors: {}, project_id: 1 }));
+    assert.doesNotThrow(() => new GitLab({ authors: {}, project_id: 1, api_token: "" }));
+  } finally {
+    console.warn = warn;
+  }
+});
+
+test("requestHeaders sends no authorization header without a token", () => {
+  assert.deepEqual(requestHeaders(undefined), {});
+  assert.deepEqual(requestHeaders(""), {});
+  assert.deepEqual(requestHeaders("glpat-123"), { authorization: "Bearer glpat-123" });
+});
@@ -0,0 +1,8 @@
+// @types/node 17 predates Node's built-in test runner, so these are the parts
+// of it the tests use. Drop this once @types/node is upgraded to 18 or later.
+declare module "node:test" {
+  type Fn = () => void | Promise<void>;
+
+  export function test(name: string, fn: Fn): Promise<void>;
+  export function afterEach(fn: Fn): void;
+}